@@ -1,45 +1,711 @@
-use proc_macro::{TokenStream};
-use syn::{parse_macro_input, DeriveInput, Data, DataStruct, Fields};
-use quote::quote;
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields,
+    Ident, Lit, LitInt, LitStr, Member, Meta, Path, Result,
+};
 
-#[proc_macro_derive(CustomDebug)]
+#[proc_macro_derive(CustomDebug, attributes(debug))]
 pub fn derive(input: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
+
+    expand(ast).unwrap_or_else(Error::into_compile_error).into()
+}
+
+#[proc_macro_derive(CustomDisplay, attributes(display))]
+pub fn derive_display(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    expand_display(ast)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(mut ast: DeriveInput) -> Result<TokenStream2> {
     let name = &ast.ident;
+    let name_str = name.to_string();
 
     let fields = match &ast.data {
         Data::Struct(s) => match &s.fields {
             Fields::Named(named_fields) => &named_fields.named,
-            _ => panic!("Currently only support named fields"),
+            _ => {
+                return Err(Error::new_spanned(
+                    &s.fields,
+                    "Currently only support named fields",
+                ))
+            }
         },
-        _ => panic!("Unsupported data type"),
+        _ => return Err(Error::new_spanned(&ast.ident, "Unsupported data type")),
     };
 
-    let mut fields_debug = fields.iter().peekable();
+    let container = ContainerAttrs::from_attrs(&ast.attrs)?;
+
     let mut debug_statements = quote! {};
+    let mut exported_fields = Vec::new();
+    let mut needs_with_wrapper = false;
+    let mut needs_limit_wrapper = false;
+    let mut needs_redacted = false;
+    let mut needs_ptr_cycle_wrapper = false;
+    let mut any_skipped = false;
+    let mut any_skipped_conditionally = false;
+    let mut default_bounds = Vec::new();
 
-    while let Some(field) = fields_debug.next() {
+    for field in fields {
         let field_name = &field.ident;
-        let debug_statement = quote! {
-            write!(f, "{}: \"{}\"", stringify!(#field_name), &self.#field_name)?;
+        let attrs = FieldAttrs::from_attrs(&field.attrs)?;
+        let field_name_str = match &attrs.rename {
+            Some(rename) => rename.value(),
+            None => field_name.as_ref().unwrap().to_string(),
         };
 
-        debug_statements.extend(debug_statement);
+        if attrs.skip {
+            any_skipped = true;
+            continue;
+        }
 
-        if fields_debug.peek().is_some() {
-            debug_statements.extend(quote! { write!(f, ", ")?; });
+        // Conditions under which the field is left out at runtime.
+        let mut skip_if = Vec::new();
+        if let Some(path) = &attrs.skip_if {
+            skip_if.push(quote! { #path(&self.#field_name) });
+        }
+        if container.skip_defaults {
+            let ty = &field.ty;
+            skip_if.push(quote! {
+                self.#field_name == <#ty as ::core::default::Default>::default()
+            });
+            default_bounds.push(ty.clone());
         }
+        let skip_condition = if skip_if.is_empty() {
+            None
+        } else {
+            any_skipped_conditionally = true;
+            Some(quote! { #(#skip_if)||* })
+        };
+
+        // Structured export carries the raw value unless it is redacted; the
+        // other formats only affect how the value reads in Debug output.
+        let exported = match attrs.format {
+            FieldFormat::Redact => quote! { (#field_name_str, &__CustomDebugRedacted) },
+            _ => quote! { (#field_name_str, &self.#field_name) },
+        };
+        exported_fields.push(match &skip_condition {
+            Some(condition) => quote! {
+                if #condition {
+                    ::core::option::Option::None
+                } else {
+                    ::core::option::Option::Some(#exported)
+                }
+            },
+            None => quote! { ::core::option::Option::Some(#exported) },
+        });
+
+        let value = match attrs.format {
+            FieldFormat::Default => quote! { &self.#field_name },
+            FieldFormat::Redact => {
+                needs_redacted = true;
+                quote! { &__CustomDebugRedacted }
+            }
+            FieldFormat::Fmt(fmt) => quote! { &::core::format_args!(#fmt, &self.#field_name) },
+            FieldFormat::With(with) => {
+                needs_with_wrapper = true;
+                quote! { &__CustomDebugWith(#with, &self.#field_name) }
+            }
+            FieldFormat::Limit(limit) => {
+                needs_limit_wrapper = true;
+                quote! { &__CustomDebugLimit(&self.#field_name, #limit) }
+            }
+            FieldFormat::PtrCycle => {
+                needs_ptr_cycle_wrapper = true;
+                quote! { &__CustomDebugPtrCycle(&self.#field_name) }
+            }
+        };
+
+        let statement = quote! {
+            debug.field(#field_name_str, #value);
+        };
+        debug_statements.extend(match &skip_condition {
+            Some(condition) if container.non_exhaustive => quote! {
+                if #condition {
+                    skipped = true;
+                } else {
+                    #statement
+                }
+            },
+            Some(condition) => quote! {
+                if !(#condition) {
+                    #statement
+                }
+            },
+            None => statement,
+        });
     }
 
+    // Formatter functions are called through a wrapper type rather than
+    // directly, so that the output still goes through `debug_struct` and picks
+    // up its indentation and the alternate flag.
+    let with_wrapper = if needs_with_wrapper {
+        quote! {
+            struct __CustomDebugWith<'a, T: ?Sized>(
+                fn(&T, &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result,
+                &'a T,
+            );
+
+            impl<'a, T: ?Sized> ::core::fmt::Debug for __CustomDebugWith<'a, T> {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    (self.0)(self.1, f)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let limit_wrapper = if needs_limit_wrapper {
+        quote! {
+            struct __CustomDebugLimit<'a, T: ?Sized>(&'a T, usize);
+
+            impl<'a, T: ?Sized> ::core::fmt::Debug for __CustomDebugLimit<'a, T>
+            where
+                &'a T: ::core::iter::IntoIterator,
+                <&'a T as ::core::iter::IntoIterator>::Item: ::core::fmt::Debug,
+            {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let mut iter = ::core::iter::IntoIterator::into_iter(self.0);
+                    let mut list = f.debug_list();
+                    list.entries(::core::iter::Iterator::take(
+                        ::core::iter::Iterator::by_ref(&mut iter),
+                        self.1,
+                    ));
+                    let more = ::core::iter::Iterator::count(iter);
+                    if more > 0 {
+                        list.entry(&::core::format_args!(".. ({} more)", more));
+                    }
+                    list.finish()
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // Pointers currently being printed on this thread, from the outermost
+    // field inward. Reaching one of them again means the graph has a cycle.
+    let ptr_cycle_wrapper = if needs_ptr_cycle_wrapper {
+        quote! {
+            struct __CustomDebugPtrCycle<'a, P>(&'a P);
+
+            trait __CustomDebugCyclePtr {
+                fn cycle_ptr(&self) -> ::core::option::Option<*const ()>;
+            }
+
+            impl<T: ?Sized> __CustomDebugCyclePtr for ::std::rc::Rc<T> {
+                fn cycle_ptr(&self) -> ::core::option::Option<*const ()> {
+                    ::core::option::Option::Some(::std::rc::Rc::as_ptr(self) as *const ())
+                }
+            }
+
+            impl<T: ?Sized> __CustomDebugCyclePtr for ::std::sync::Arc<T> {
+                fn cycle_ptr(&self) -> ::core::option::Option<*const ()> {
+                    ::core::option::Option::Some(::std::sync::Arc::as_ptr(self) as *const ())
+                }
+            }
+
+            impl<P: __CustomDebugCyclePtr> __CustomDebugCyclePtr for ::core::option::Option<P> {
+                fn cycle_ptr(&self) -> ::core::option::Option<*const ()> {
+                    self.as_ref().and_then(__CustomDebugCyclePtr::cycle_ptr)
+                }
+            }
+
+            ::std::thread_local! {
+                static __CUSTOM_DEBUG_VISITED: ::core::cell::RefCell<::std::vec::Vec<*const ()>> =
+                    const { ::core::cell::RefCell::new(::std::vec::Vec::new()) };
+            }
+
+            struct __CustomDebugVisitedGuard;
+
+            impl ::core::ops::Drop for __CustomDebugVisitedGuard {
+                fn drop(&mut self) {
+                    __CUSTOM_DEBUG_VISITED.with(|visited| visited.borrow_mut().pop());
+                }
+            }
+
+            impl<'a, P> ::core::fmt::Debug for __CustomDebugPtrCycle<'a, P>
+            where
+                P: __CustomDebugCyclePtr + ::core::fmt::Debug,
+            {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    let ptr = match __CustomDebugCyclePtr::cycle_ptr(self.0) {
+                        ::core::option::Option::Some(ptr) => ptr,
+                        ::core::option::Option::None => return ::core::fmt::Debug::fmt(self.0, f),
+                    };
+                    if __CUSTOM_DEBUG_VISITED.with(|visited| visited.borrow().contains(&ptr)) {
+                        return f.write_str("<cycle>");
+                    }
+                    __CUSTOM_DEBUG_VISITED.with(|visited| visited.borrow_mut().push(ptr));
+                    let _guard = __CustomDebugVisitedGuard;
+                    ::core::fmt::Debug::fmt(self.0, f)
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // How many values of this type are currently being printed on this
+    // thread, one inside the other.
+    let depth_counter = if container.max_depth.is_some() {
+        quote! {
+            ::std::thread_local! {
+                static __CUSTOM_DEBUG_DEPTH: ::core::cell::Cell<usize> = const { ::core::cell::Cell::new(0) };
+            }
+
+            struct __CustomDebugDepthGuard;
+
+            impl ::core::ops::Drop for __CustomDebugDepthGuard {
+                fn drop(&mut self) {
+                    __CUSTOM_DEBUG_DEPTH.with(|depth| depth.set(depth.get() - 1));
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let depth_check = match &container.max_depth {
+        Some(max_depth) => quote! {
+            let depth = __CUSTOM_DEBUG_DEPTH.with(|depth| depth.replace(depth.get() + 1));
+            let _guard = __CustomDebugDepthGuard;
+            if depth >= #max_depth {
+                return f.write_str("..");
+            }
+        },
+        None => quote! {},
+    };
+
+    let redacted = if needs_redacted {
+        quote! {
+            struct __CustomDebugRedacted;
+
+            impl ::core::fmt::Debug for __CustomDebugRedacted {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    f.write_str("[REDACTED]")
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let (track_skipped, finish) = if container.non_exhaustive && any_skipped {
+        (quote! {}, quote! { debug.finish_non_exhaustive() })
+    } else if container.non_exhaustive && any_skipped_conditionally {
+        (
+            quote! { let mut skipped = false; },
+            quote! {
+                if skipped {
+                    debug.finish_non_exhaustive()
+                } else {
+                    debug.finish()
+                }
+            },
+        )
+    } else {
+        (quote! {}, quote! { debug.finish() })
+    };
+
+    for param in ast.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::core::fmt::Debug));
+    }
+    for ty in default_bounds {
+        ast.generics
+            .make_where_clause()
+            .predicates
+            .push(parse_quote! {
+                #ty: ::core::cmp::PartialEq + ::core::default::Default
+            });
+    }
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let debug_fields = if container.fields {
+        let len = exported_fields.len();
+        quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                /// The fields printed by this type's `Debug` impl, as name and
+                /// value pairs for structured logging.
+                pub fn debug_fields(
+                    &self,
+                ) -> impl ::core::iter::Iterator<Item = (&'static str, &dyn ::core::fmt::Debug)> + '_
+                {
+                    let fields: [::core::option::Option<(&'static str, &dyn ::core::fmt::Debug)>; #len] = [
+                        #(#exported_fields,)*
+                    ];
+                    ::core::iter::Iterator::flatten(::core::iter::IntoIterator::into_iter(fields))
+                }
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    // The helper types are defined once, outside of the impls, so that the
+    // Debug impl and `debug_fields` agree on them. They share a scope with the
+    // caller's items, hence the `__CustomDebug` prefix on their names.
     let gen = quote! {
-        impl std::fmt::Debug for #name {
-            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-                write!(f, "{} {{ ", stringify!(#name))?;
-                #debug_statements
-                write!(f, " }}")
+        const _: () = {
+            #with_wrapper
+            #limit_wrapper
+            #ptr_cycle_wrapper
+            #redacted
+            #depth_counter
+
+            impl #impl_generics ::core::fmt::Debug for #name #ty_generics #where_clause {
+                fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                    #depth_check
+                    let mut debug = f.debug_struct(#name_str);
+                    #track_skipped
+                    #debug_statements
+                    #finish
+                }
+            }
+
+            #debug_fields
+        };
+    };
+
+    Ok(gen)
+}
+
+/// Attributes on the struct itself, `#[debug(...)]`.
+struct ContainerAttrs {
+    /// `#[debug(non_exhaustive)]`: end the output with `..` when any field is
+    /// skipped.
+    non_exhaustive: bool,
+    /// `#[debug(skip_defaults)]`: leave out every field that is equal to its
+    /// type's `Default::default()`.
+    skip_defaults: bool,
+    /// `#[debug(fields)]`: also generate a `debug_fields` method listing the
+    /// printed fields.
+    fields: bool,
+    /// `#[debug(max_depth = N)]`: print `..` in place of a value nested
+    /// inside N others of the same type. Requires std.
+    max_depth: Option<LitInt>,
+}
+
+impl ContainerAttrs {
+    fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut non_exhaustive = false;
+        let mut skip_defaults = false;
+        let mut fields = false;
+        let mut max_depth = None;
+
+        for attr in attrs {
+            if !attr.path().is_ident("debug") {
+                continue;
             }
+
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("non_exhaustive") {
+                    non_exhaustive = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip_defaults") {
+                    skip_defaults = true;
+                    Ok(())
+                } else if meta.path.is_ident("fields") {
+                    fields = true;
+                    Ok(())
+                } else if meta.path.is_ident("max_depth") {
+                    let limit: LitInt = meta.value()?.parse()?;
+                    limit.base10_parse::<usize>()?;
+                    max_depth = Some(limit);
+                    Ok(())
+                } else {
+                    Err(meta.error("unrecognized `debug` attribute"))
+                }
+            })?;
         }
+
+        Ok(ContainerAttrs {
+            non_exhaustive,
+            skip_defaults,
+            fields,
+            max_depth,
+        })
+    }
+}
+
+/// Attributes on a single field, `#[debug = "..."]` or `#[debug(...)]`.
+struct FieldAttrs {
+    format: FieldFormat,
+    /// `#[debug(skip)]`: leave the field out of the output.
+    skip: bool,
+    /// `#[debug(skip_if = "path")]`: leave the field out whenever
+    /// `path(&field)` returns true.
+    skip_if: Option<Path>,
+    /// `#[debug(rename = "...")]`: the name to print instead of the field's.
+    rename: Option<LitStr>,
+}
+
+/// How a single field is rendered.
+enum FieldFormat {
+    /// No attribute: the field's own `Debug` impl.
+    Default,
+    /// `#[debug = "0b{:08b}"]`: a format string applied to the field.
+    Fmt(LitStr),
+    /// `#[debug(with = "path::to::fn")]`: a function with the signature
+    /// `fn(&T, &mut fmt::Formatter) -> fmt::Result`.
+    With(Path),
+    /// `#[debug(limit = N)]`: the first N items of a collection followed by a
+    /// count of the rest.
+    Limit(LitInt),
+    /// `#[debug(redact)]`: a placeholder instead of the value.
+    Redact,
+    /// `#[debug(ptr_cycle)]`: an `Rc` or `Arc`, possibly in an `Option`,
+    /// printed as `<cycle>` if it points back to a value already being
+    /// printed. Requires std.
+    PtrCycle,
+}
+
+impl FieldAttrs {
+    fn from_attrs(attrs: &[Attribute]) -> Result<Self> {
+        let mut format = FieldFormat::Default;
+        let mut skip = false;
+        let mut skip_if = None;
+        let mut rename = None;
+
+        let mut set_format = |attr: &dyn quote::ToTokens, parsed| {
+            if !matches!(format, FieldFormat::Default) {
+                return Err(Error::new_spanned(
+                    attr,
+                    "conflicting `debug` formats on this field",
+                ));
+            }
+            format = parsed;
+            Ok(())
+        };
+
+        for attr in attrs {
+            if !attr.path().is_ident("debug") {
+                continue;
+            }
+
+            match &attr.meta {
+                Meta::NameValue(nv) => match &nv.value {
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Str(fmt), ..
+                    }) => set_format(attr, FieldFormat::Fmt(fmt.clone()))?,
+                    value => return Err(Error::new_spanned(value, "expected a format string")),
+                },
+                Meta::List(_) => attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("with") {
+                        let path: LitStr = meta.value()?.parse()?;
+                        set_format(&meta.path, FieldFormat::With(path.parse()?))
+                    } else if meta.path.is_ident("limit") {
+                        let limit: LitInt = meta.value()?.parse()?;
+                        limit.base10_parse::<usize>()?;
+                        set_format(&meta.path, FieldFormat::Limit(limit))
+                    } else if meta.path.is_ident("redact") {
+                        set_format(&meta.path, FieldFormat::Redact)
+                    } else if meta.path.is_ident("ptr_cycle") {
+                        set_format(&meta.path, FieldFormat::PtrCycle)
+                    } else if meta.path.is_ident("rename") {
+                        rename = Some(meta.value()?.parse::<LitStr>()?);
+                        Ok(())
+                    } else if meta.path.is_ident("skip_if") {
+                        let path: LitStr = meta.value()?.parse()?;
+                        skip_if = Some(path.parse()?);
+                        Ok(())
+                    } else if meta.path.is_ident("skip") {
+                        skip = true;
+                        Ok(())
+                    } else {
+                        Err(meta.error("unrecognized `debug` attribute"))
+                    }
+                })?,
+                Meta::Path(_) => {
+                    return Err(Error::new_spanned(
+                        attr,
+                        "expected `debug = \"...\"` or `debug(...)`",
+                    ))
+                }
+            }
+        }
+
+        Ok(FieldAttrs {
+            format,
+            skip,
+            skip_if,
+            rename,
+        })
+    }
+}
+
+fn expand_display(ast: DeriveInput) -> Result<TokenStream2> {
+    let name = &ast.ident;
+
+    let arms = match &ast.data {
+        Data::Struct(s) => {
+            let template = display_template(&ast.attrs, &ast.ident)?;
+            vec![display_arm(quote!(Self), &s.fields, &template)?]
+        }
+        Data::Enum(e) => e
+            .variants
+            .iter()
+            .map(|variant| {
+                let ident = &variant.ident;
+                let template = display_template(&variant.attrs, ident)?;
+                display_arm(quote!(Self::#ident), &variant.fields, &template)
+            })
+            .collect::<Result<_>>()?,
+        Data::Union(u) => {
+            return Err(Error::new_spanned(
+                u.union_token,
+                "CustomDisplay does not support unions",
+            ))
+        }
+    };
+
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Display for #name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match self {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+/// Finds the `#[display("...")]` attribute on a struct or enum variant.
+fn display_template(attrs: &[Attribute], item: &Ident) -> Result<LitStr> {
+    let mut template = None;
+
+    for attr in attrs {
+        if !attr.path().is_ident("display") {
+            continue;
+        }
+        if template.is_some() {
+            return Err(Error::new_spanned(attr, "duplicate `display` attribute"));
+        }
+        template = Some(attr.parse_args::<LitStr>()?);
+    }
+
+    template.ok_or_else(|| {
+        Error::new_spanned(item, format!("missing `#[display(\"...\")]` on `{}`", item))
+    })
+}
+
+/// Generates the match arm formatting one struct or variant.
+///
+/// Field references in the template are checked against `fields` and
+/// rewritten to name the bindings of the arm's pattern, so that `{0}` on a
+/// tuple variant refers to its first field rather than to a positional
+/// argument of `write!`.
+fn display_arm(path: TokenStream2, fields: &Fields, template: &LitStr) -> Result<TokenStream2> {
+    let source = template.value();
+    let mut rewritten = String::new();
+    let mut used = Vec::new();
+    let mut chars = source.chars().peekable();
+
+    let mut resolve = |arg: &str| -> Result<String> {
+        let member = fields
+            .iter()
+            .enumerate()
+            .find_map(|(i, field)| match &field.ident {
+                Some(ident) if ident == arg => Some(Member::Named(ident.clone())),
+                None if arg == i.to_string() => Some(Member::from(i)),
+                _ => None,
+            })
+            .ok_or_else(|| {
+                let message = if arg.is_empty() {
+                    "placeholders in a display template must name a field".to_owned()
+                } else {
+                    format!("no field `{}` in display template", arg)
+                };
+                Error::new(template.span(), message)
+            })?;
+
+        let binding = match &member {
+            Member::Named(ident) => ident.clone(),
+            Member::Unnamed(index) => format_ident!("_{}", index.index),
+        };
+        let name = binding.to_string();
+        if !used.iter().any(|(_, b): &(Member, Ident)| *b == binding) {
+            used.push((member, binding));
+        }
+        Ok(name)
     };
 
-    gen.into()
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                rewritten.push_str("{{");
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                rewritten.push_str("}}");
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => placeholder.push(ch),
+                        None => {
+                            return Err(Error::new(
+                                template.span(),
+                                "unmatched `{` in display template",
+                            ))
+                        }
+                    }
+                }
+
+                let (arg, spec) = match placeholder.split_once(':') {
+                    Some((arg, spec)) => (arg, Some(spec)),
+                    None => (placeholder.as_str(), None),
+                };
+
+                rewritten.push('{');
+                rewritten.push_str(&resolve(arg.trim())?);
+                if let Some(spec) = spec {
+                    rewritten.push(':');
+                    // Width and precision may also come from a field, as in
+                    // `{value:>width$}`.
+                    let mut rest = spec;
+                    while let Some(dollar) = rest.find('$') {
+                        let start = rest[..dollar]
+                            .rfind(|c: char| !c.is_alphanumeric() && c != '_')
+                            .map_or(0, |i| i + 1);
+                        rewritten.push_str(&rest[..start]);
+                        rewritten.push_str(&resolve(&rest[start..dollar])?);
+                        rewritten.push('$');
+                        rest = &rest[dollar + 1..];
+                    }
+                    rewritten.push_str(rest);
+                }
+                rewritten.push('}');
+            }
+            '}' => {
+                return Err(Error::new(
+                    template.span(),
+                    "unmatched `}` in display template",
+                ))
+            }
+            ch => rewritten.push(ch),
+        }
+    }
+
+    let rewritten = LitStr::new(&rewritten, template.span());
+    let patterns = used.iter().map(|(member, binding)| match member {
+        Member::Named(ident) => quote!(#ident),
+        Member::Unnamed(index) => quote!(#index: #binding),
+    });
+    let args = used.iter().map(|(_, binding)| quote!(#binding = #binding));
+
+    Ok(quote! {
+        #path { #(#patterns,)* .. } => ::core::write!(f, #rewritten #(, #args)*),
+    })
 }
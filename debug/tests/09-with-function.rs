@@ -0,0 +1,53 @@
+// A format string is not enough for fields that need custom logic to print,
+// like a byte buffer shown as hex. Accept #[debug(with = "path::to::fn")] on a
+// field, where the function has the signature
+//
+//     fn(&T, &mut fmt::Formatter) -> fmt::Result
+//
+// The function is invoked through a wrapper type that implements Debug, so the
+// field is still passed to `debug_struct` and the caller's formatter, including
+// the alternate `{:#?}` flag, reaches the custom function.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+
+mod hex {
+    use std::fmt;
+
+    pub fn bytes(bytes: &[u8], f: &mut fmt::Formatter) -> fmt::Result {
+        if f.alternate() {
+            f.write_str("0x")?;
+        }
+        for byte in bytes {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+fn upper(value: &&'static str, f: &mut fmt::Formatter) -> fmt::Result {
+    f.write_str(&value.to_uppercase())
+}
+
+#[derive(CustomDebug)]
+pub struct Packet {
+    #[debug(with = "upper")]
+    name: &'static str,
+    #[debug(with = "hex::bytes")]
+    payload: Vec<u8>,
+}
+
+fn main() {
+    let packet = Packet {
+        name: "ping",
+        payload: vec![0xde, 0xad, 0xbe, 0xef],
+    };
+
+    let debug = format!("{:?}", packet);
+    let expected = r#"Packet { name: PING, payload: deadbeef }"#;
+    assert_eq!(debug, expected);
+
+    let debug = format!("{:#?}", packet);
+    let expected = "Packet {\n    name: PING,\n    payload: 0xdeadbeef,\n}";
+    assert_eq!(debug, expected);
+}
@@ -0,0 +1,42 @@
+// Debug output of a struct holding a very large collection is rarely useful in
+// full. Accept #[debug(limit = N)] on a collection field to print only its
+// first N items, followed by a count of how many were left out.
+//
+// Any field type works as long as a reference to it can be iterated and the
+// items implement Debug, so this covers Vec, slices, sets and maps alike.
+
+use derive_debug::CustomDebug;
+use std::collections::BTreeMap;
+
+#[derive(CustomDebug)]
+pub struct Trace {
+    id: u32,
+    #[debug(limit = 3)]
+    samples: Vec<u32>,
+    #[debug(limit = 2)]
+    labels: BTreeMap<&'static str, u8>,
+}
+
+fn main() {
+    let trace = Trace {
+        id: 7,
+        samples: (0..100).collect(),
+        labels: [("a", 1), ("b", 2)].into_iter().collect(),
+    };
+
+    let debug = format!("{:?}", trace);
+    let expected =
+        r#"Trace { id: 7, samples: [0, 1, 2, .. (97 more)], labels: [("a", 1), ("b", 2)] }"#;
+    assert_eq!(debug, expected);
+
+    let debug = format!(
+        "{:#?}",
+        Trace {
+            samples: vec![1, 2, 3, 4],
+            labels: BTreeMap::new(),
+            ..trace
+        }
+    );
+    let expected = "Trace {\n    id: 7,\n    samples: [\n        1,\n        2,\n        3,\n        .. (1 more),\n    ],\n    labels: [],\n}";
+    assert_eq!(debug, expected);
+}
@@ -0,0 +1,46 @@
+// Fields marked #[debug(skip)] are left out of the output entirely. When the
+// struct also carries #[debug(non_exhaustive)], a skipped field makes the
+// output end in `..` via `DebugStruct::finish_non_exhaustive`, so a reader of
+// the logs can tell that something was omitted.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(non_exhaustive)]
+pub struct Connection {
+    host: &'static str,
+    #[debug(skip)]
+    password: &'static str,
+}
+
+#[derive(CustomDebug)]
+#[debug(non_exhaustive)]
+pub struct Complete {
+    host: &'static str,
+}
+
+#[derive(CustomDebug)]
+pub struct Quiet {
+    host: &'static str,
+    #[debug(skip)]
+    password: &'static str,
+}
+
+fn main() {
+    let conn = Connection {
+        host: "db",
+        password: "hunter2",
+    };
+    assert_eq!(format!("{:?}", conn), r#"Connection { host: "db", .. }"#);
+    let _ = conn.password;
+
+    let complete = Complete { host: "db" };
+    assert_eq!(format!("{:?}", complete), r#"Complete { host: "db" }"#);
+
+    let quiet = Quiet {
+        host: "db",
+        password: "hunter2",
+    };
+    assert_eq!(format!("{:?}", quiet), r#"Quiet { host: "db" }"#);
+    let _ = quiet.password;
+}
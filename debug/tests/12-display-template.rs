@@ -0,0 +1,64 @@
+// Alongside CustomDebug, a CustomDisplay derive generates a Display impl from a
+// #[display("...")] template on the struct, or on each variant of an enum.
+//
+// Placeholders name the fields of the struct or variant they are attached to,
+// using the same syntax as format strings: `{name}` for a named field, `{0}`
+// for the first field of a tuple variant, followed by an optional format spec
+// like `{id:04}`. Width and precision may refer to fields too, as in
+// `{value:>width$}`.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+#[display("{name} ({id:04})")]
+pub struct User {
+    id: u32,
+    name: String,
+}
+
+#[derive(CustomDisplay)]
+pub enum Error {
+    #[display("file not found: {0}")]
+    NotFound(&'static str),
+    #[display("expected {expected}, found {found} in {{line {line}}}")]
+    Mismatch {
+        expected: char,
+        found: char,
+        line: usize,
+    },
+    #[display("[{value:>width$}]")]
+    Padded { value: u8, width: usize },
+    #[display("timed out")]
+    Timeout,
+}
+
+#[derive(CustomDisplay)]
+#[display("<{0}>")]
+pub struct Wrapper<T: std::fmt::Display>(T);
+
+fn main() {
+    let user = User {
+        id: 42,
+        name: "ferris".to_owned(),
+    };
+    assert_eq!(user.to_string(), "ferris (0042)");
+
+    assert_eq!(
+        Error::NotFound("a.txt").to_string(),
+        "file not found: a.txt"
+    );
+
+    let mismatch = Error::Mismatch {
+        expected: '(',
+        found: ']',
+        line: 3,
+    };
+    assert_eq!(mismatch.to_string(), "expected (, found ] in {line 3}");
+
+    let padded = Error::Padded { value: 7, width: 3 };
+    assert_eq!(padded.to_string(), "[  7]");
+
+    assert_eq!(Error::Timeout.to_string(), "timed out");
+
+    assert_eq!(Wrapper(user).to_string(), "<ferris (0042)>");
+}
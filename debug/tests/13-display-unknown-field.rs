@@ -0,0 +1,15 @@
+// Field references in a #[display("...")] template are checked while the derive
+// expands, so that a typo is reported on the template that contains it rather
+// than as a confusing error from inside the generated `write!`.
+
+use derive_debug::CustomDisplay;
+
+#[derive(CustomDisplay)]
+pub enum Error {
+    #[display("file not found: {path}")]
+    NotFound { path: String },
+    #[display("expected {expected}, found {fuond}")]
+    Mismatch { expected: char, found: char },
+}
+
+fn main() {}
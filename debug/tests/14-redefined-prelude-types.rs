@@ -0,0 +1,73 @@
+// Does the derive still work if some of the standard library prelude item
+// names mean something different in the caller's code?
+//
+// Like the builder's test of the same name, every path in the generated impls
+// should be absolute. Here they go through ::core rather than ::std, so that
+// the same output is usable from #![no_std] crates as well.
+
+use derive_debug::{CustomDebug, CustomDisplay};
+
+type Option = ();
+type Some = ();
+type None = ();
+type Result = ();
+type Ok = ();
+type Err = ();
+type Debug = ();
+type Display = ();
+type IntoIterator = ();
+type Iterator = ();
+
+mod fmt {}
+mod core {}
+mod std {}
+
+#[allow(unused_macros)]
+macro_rules! write {
+    () => {};
+}
+
+#[allow(unused_macros)]
+macro_rules! format_args {
+    () => {};
+}
+
+fn hex(value: &u8, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+    ::core::write!(f, "{:#x}", value)
+}
+
+#[derive(CustomDebug)]
+pub struct Field<T> {
+    value: T,
+    #[debug = "0b{:08b}"]
+    bitmask: u8,
+    #[debug(with = "hex")]
+    code: u8,
+    #[debug(limit = 1)]
+    items: Vec<u8>,
+}
+
+#[derive(CustomDisplay)]
+#[display("{name}={value}")]
+pub struct Pair {
+    name: &'static str,
+    value: u8,
+}
+
+fn main() {
+    let field = Field {
+        value: "F",
+        bitmask: 0b00011100,
+        code: 255,
+        items: vec![1, 2],
+    };
+    let expected =
+        r#"Field { value: "F", bitmask: 0b00011100, code: 0xff, items: [1, .. (1 more)] }"#;
+    assert_eq!(::std::format!("{:?}", field), expected);
+
+    let pair = Pair {
+        name: "x",
+        value: 1,
+    };
+    assert_eq!(::std::format!("{}", pair), "x=1");
+}
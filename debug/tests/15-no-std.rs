@@ -0,0 +1,84 @@
+// The generated impls only refer to ::core, so both derives can be used from a
+// #![no_std] crate. Nothing at the root of this crate can name `std`; the
+// `runtime` module links it only to provide the entry point and panic handler
+// that a test binary needs.
+
+#![no_std]
+
+mod runtime {
+    extern crate std;
+}
+
+use core::fmt::{self, Write};
+use derive_debug::{CustomDebug, CustomDisplay};
+
+#[derive(CustomDebug)]
+pub struct Register {
+    #[debug = "{:#06x}"]
+    address: u16,
+    #[debug(limit = 2)]
+    history: [u8; 4],
+    #[debug(skip)]
+    dirty: bool,
+}
+
+#[derive(CustomDisplay)]
+pub enum Fault {
+    #[display("bus fault at {address:#x}")]
+    Bus { address: u32 },
+    #[display("watchdog")]
+    Watchdog,
+}
+
+struct Buffer {
+    bytes: [u8; 128],
+    len: usize,
+}
+
+impl Buffer {
+    fn new() -> Self {
+        Buffer {
+            bytes: [0; 128],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.bytes[..self.len]).unwrap()
+    }
+}
+
+impl Write for Buffer {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+fn main() {
+    let register = Register {
+        address: 0x40,
+        history: [1, 2, 3, 4],
+        dirty: true,
+    };
+    let _ = register.dirty;
+
+    let mut buffer = Buffer::new();
+    write!(buffer, "{:?}", register).unwrap();
+    assert_eq!(
+        buffer.as_str(),
+        "Register { address: 0x0040, history: [1, 2, .. (2 more)] }",
+    );
+
+    let mut buffer = Buffer::new();
+    write!(
+        buffer,
+        "{}, {}",
+        Fault::Bus { address: 0xff },
+        Fault::Watchdog
+    )
+    .unwrap();
+    assert_eq!(buffer.as_str(), "bus fault at 0xff, watchdog");
+}
@@ -0,0 +1,91 @@
+// For structured logging, #[debug(fields)] on the struct additionally generates
+//
+//     pub fn debug_fields(&self) -> impl Iterator<Item = (&'static str, &dyn Debug)>
+//
+// listing the same fields as the Debug impl, so that a logger can emit them as
+// key/value pairs without a second, hand-maintained list.
+//
+// It follows the attributes that decide which fields appear and under what
+// name: #[debug(skip)] fields are left out, #[debug(rename = "...")] changes the
+// key, and #[debug(redact)] fields keep their key but never expose the value.
+// Format strings, formatter functions and limits only change how a value reads
+// in Debug output, so for those the raw field value is exported.
+//
+// The helper types behind these formats must not get in the way of the
+// caller's own types of the same name, such as the `Redacted` below.
+
+use derive_debug::CustomDebug;
+use std::fmt;
+
+#[derive(CustomDebug)]
+#[debug(fields)]
+pub struct Request {
+    #[debug(rename = "http.method")]
+    method: &'static str,
+    #[debug = "{:#x}"]
+    status: u16,
+    #[debug(redact)]
+    token: &'static str,
+    #[debug(skip)]
+    body: Vec<u8>,
+}
+
+pub struct DebugWith;
+
+impl DebugWith {
+    fn short(value: &u8, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", value)
+    }
+}
+
+#[derive(CustomDebug)]
+#[debug(fields)]
+pub struct Redacted {
+    #[debug(redact)]
+    user: u8,
+    #[debug(with = "DebugWith::short")]
+    previous: u8,
+}
+
+#[derive(CustomDebug)]
+pub struct DebugLimit {
+    #[debug(limit = 1)]
+    entries: Vec<u8>,
+}
+
+fn main() {
+    let request = Request {
+        method: "GET",
+        status: 200,
+        token: "secret",
+        body: vec![1, 2, 3],
+    };
+    let _ = (request.token, &request.body);
+
+    let debug = format!("{:?}", request);
+    let expected = r#"Request { http.method: "GET", status: 0xc8, token: [REDACTED] }"#;
+    assert_eq!(debug, expected);
+
+    let pairs: Vec<String> = request
+        .debug_fields()
+        .map(|(name, value)| format!("{}={:?}", name, value))
+        .collect();
+    assert_eq!(
+        pairs,
+        ["http.method=\"GET\"", "status=200", "token=[REDACTED]"]
+    );
+
+    let redacted = Redacted {
+        user: 1,
+        previous: 2,
+    };
+    let debug = format!("{:?}", redacted);
+    assert_eq!(debug, "Redacted { user: [REDACTED], previous: #2 }");
+    assert_eq!(redacted.debug_fields().count(), 2);
+
+    let limit = DebugLimit {
+        entries: vec![3, 4],
+    };
+    let debug = format!("{:?}", limit);
+    assert_eq!(debug, "DebugLimit { entries: [3, .. (1 more)] }");
+}
@@ -0,0 +1,116 @@
+// Recursive types can produce Debug output that is too deep to be useful, or
+// that never ends at all when values form a cycle through shared pointers.
+//
+// #[debug(max_depth = N)] on a struct counts, in a thread-local, how many values
+// of that type are currently being printed one inside the other, and prints
+// `..` in place of any value nested inside N others.
+//
+// #[debug(ptr_cycle)] on an Rc or Arc field, possibly wrapped in an Option,
+// remembers which pointers are being printed and prints `<cycle>` when a
+// pointer refers back to one of them instead of following it again.
+//
+// Both rely on std's thread-locals, so unlike the other attributes they are not
+// available to #![no_std] crates.
+//
+// The thread-locals and guard types behind them must not get in the way of
+// the caller's own items of the same name.
+
+use derive_debug::CustomDebug;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(CustomDebug)]
+#[debug(max_depth = 2)]
+pub struct One<T> {
+    value: T,
+    two: Option<Box<Two<T>>>,
+}
+
+#[derive(CustomDebug)]
+struct Two<T> {
+    one: Box<One<T>>,
+}
+
+#[derive(CustomDebug)]
+struct Node {
+    name: &'static str,
+    #[debug(ptr_cycle)]
+    next: Option<Rc<RefCell<Node>>>,
+}
+
+#[derive(CustomDebug)]
+struct Shared {
+    #[debug(ptr_cycle)]
+    first: Arc<u8>,
+    #[debug(ptr_cycle)]
+    second: Arc<u8>,
+}
+
+#[derive(CustomDebug)]
+#[debug(max_depth = 1)]
+struct DepthGuard {
+    #[debug(ptr_cycle)]
+    inner: Option<Rc<DepthGuard>>,
+}
+
+#[derive(CustomDebug)]
+struct DebugPtrCycle {
+    #[debug(ptr_cycle)]
+    value: Arc<u8>,
+}
+
+fn main() {
+    let one = One {
+        value: 1,
+        two: Some(Box::new(Two {
+            one: Box::new(One {
+                value: 2,
+                two: Some(Box::new(Two {
+                    one: Box::new(One {
+                        value: 3,
+                        two: None,
+                    }),
+                })),
+            }),
+        })),
+    };
+    let debug = format!("{:?}", one);
+    let expected =
+        "One { value: 1, two: Some(Two { one: One { value: 2, two: Some(Two { one: .. }) } }) }";
+    assert_eq!(debug, expected);
+
+    // The depth is released again after printing, even on the same thread.
+    assert_eq!(format!("{:?}", one), expected);
+
+    let a = Rc::new(RefCell::new(Node {
+        name: "a",
+        next: None,
+    }));
+    let b = Rc::new(RefCell::new(Node {
+        name: "b",
+        next: Some(a.clone()),
+    }));
+    a.borrow_mut().next = Some(b.clone());
+
+    let debug = format!("{:?}", a.borrow());
+    let expected = r#"Node { name: "a", next: Some(RefCell { value: Node { name: "b", next: Some(RefCell { value: Node { name: "a", next: <cycle> } }) } }) }"#;
+    assert_eq!(debug, expected);
+    a.borrow_mut().next = None;
+
+    // Pointers that are shared but not nested are printed in full each time.
+    let value = Arc::new(7);
+    let shared = Shared {
+        first: value.clone(),
+        second: value,
+    };
+    assert_eq!(format!("{:?}", shared), "Shared { first: 7, second: 7 }");
+
+    let guard = DepthGuard {
+        inner: Some(Rc::new(DepthGuard { inner: None })),
+    };
+    assert_eq!(format!("{:?}", guard), "DepthGuard { inner: Some(..) }");
+
+    let cycle = DebugPtrCycle { value: Arc::new(8) };
+    assert_eq!(format!("{:?}", cycle), "DebugPtrCycle { value: 8 }");
+}
@@ -0,0 +1,66 @@
+// Fields that hold nothing interesting most of the time make Debug output
+// noisy. #[debug(skip_if = "path")] leaves a field out whenever `path(&field)`
+// returns true, and #[debug(skip_defaults)] on the struct leaves out every
+// field that is equal to its type's `Default::default()`.
+//
+// Fields are still printed as soon as they hold a value. With
+// #[debug(non_exhaustive)], output ends in `..` only when some field was
+// actually left out, and debug_fields() follows the same conditions.
+
+use derive_debug::CustomDebug;
+
+#[derive(CustomDebug)]
+#[debug(non_exhaustive, fields)]
+pub struct Span {
+    name: &'static str,
+    #[debug(skip_if = "Option::is_none")]
+    parent: Option<u64>,
+    #[debug(skip_if = "Vec::is_empty")]
+    tags: Vec<&'static str>,
+}
+
+#[derive(CustomDebug)]
+#[debug(skip_defaults)]
+pub struct Config<T> {
+    name: String,
+    retries: u32,
+    extra: T,
+}
+
+fn main() {
+    let span = Span {
+        name: "request",
+        parent: None,
+        tags: Vec::new(),
+    };
+    assert_eq!(format!("{:?}", span), r#"Span { name: "request", .. }"#);
+    let names: Vec<&str> = span.debug_fields().map(|(name, _)| name).collect();
+    assert_eq!(names, ["name"]);
+
+    let span = Span {
+        name: "request",
+        parent: Some(7),
+        tags: vec!["db"],
+    };
+    let expected = r#"Span { name: "request", parent: Some(7), tags: ["db"] }"#;
+    assert_eq!(format!("{:?}", span), expected);
+    let names: Vec<&str> = span.debug_fields().map(|(name, _)| name).collect();
+    assert_eq!(names, ["name", "parent", "tags"]);
+
+    let config = Config {
+        name: "server".to_owned(),
+        retries: 0,
+        extra: false,
+    };
+    assert_eq!(format!("{:?}", config), r#"Config { name: "server" }"#);
+
+    let config = Config {
+        name: String::new(),
+        retries: 3,
+        extra: true,
+    };
+    assert_eq!(
+        format!("{:?}", config),
+        "Config { retries: 3, extra: true }"
+    );
+}
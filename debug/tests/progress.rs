@@ -4,9 +4,19 @@ fn tests() {
     t.pass("tests/01-parse.rs");
     t.pass("tests/02-impl-debug.rs");
     t.pass("tests/03-custom-format.rs");
-    //t.pass("tests/04-type-parameter.rs");
+    t.pass("tests/04-type-parameter.rs");
     //t.pass("tests/05-phantom-data.rs");
-    //t.pass("tests/06-bound-trouble.rs");
+    t.pass("tests/06-bound-trouble.rs");
     //t.pass("tests/07-associated-type.rs");
     //t.pass("tests/08-escape-hatch.rs");
+    t.pass("tests/09-with-function.rs");
+    t.pass("tests/10-collection-limit.rs");
+    t.pass("tests/11-non-exhaustive.rs");
+    t.pass("tests/12-display-template.rs");
+    t.compile_fail("tests/13-display-unknown-field.rs");
+    t.pass("tests/14-redefined-prelude-types.rs");
+    t.pass("tests/15-no-std.rs");
+    t.pass("tests/16-debug-fields.rs");
+    t.pass("tests/17-recursion-limits.rs");
+    t.pass("tests/18-conditional-skip.rs");
 }
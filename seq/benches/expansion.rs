@@ -0,0 +1,111 @@
+// Times how long seq! takes to expand 10,000-iteration bodies, by building a
+// small crate that uses it and the same crate with the expansion written out
+// by hand. The difference between the two is the cost of seq! itself.
+//
+//     cargo bench --bench expansion
+//
+// Each crate is checked a few times and the fastest run is reported, after a
+// first build that compiles seq and its dependencies.
+
+use std::env;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: usize = 10_000;
+const RUNS: usize = 3;
+
+struct Case {
+    name: &'static str,
+    /// The body of the crate using seq!.
+    seq: &'static str,
+    /// Writes out what `seq` expands to for iteration `n`.
+    expanded: fn(&mut String, usize),
+}
+
+const CASES: &[Case] = &[
+    Case {
+        name: "paste and expression",
+        seq: "seq::seq!(N in 0..10000 { pub const C~N: usize = #(N * 4); });",
+        expanded: |out, n| writeln!(out, "pub const C{}: usize = {};", n, n * 4).unwrap(),
+    },
+    Case {
+        name: "section",
+        seq: "pub static TABLE: [(u32, u32); 10000] = seq::seq!(N in 0..10000 { \
+              [#((N, #(N * 3 % 7)),)*] });",
+        expanded: |out, n| {
+            if n == 0 {
+                out.push_str("pub static TABLE: [(u32, u32); 10000] = [\n");
+            }
+            writeln!(out, "({}, {}),", n, n * 3 % 7).unwrap();
+            if n + 1 == ITERATIONS {
+                out.push_str("];\n");
+            }
+        },
+    },
+];
+
+fn main() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("seq-expansion");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    let manifest = format!(
+        "[package]\n\
+         name = \"seq-expansion\"\n\
+         version = \"0.0.0\"\n\
+         edition = \"2021\"\n\
+         publish = false\n\
+         \n\
+         [lib]\n\
+         path = \"src/lib.rs\"\n\
+         \n\
+         [dependencies]\n\
+         seq = {{ path = {:?} }}\n\
+         \n\
+         [workspace]\n",
+        env!("CARGO_MANIFEST_DIR"),
+    );
+    fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+
+    check(&dir, "");
+
+    for case in CASES {
+        let mut expanded = String::new();
+        for n in 0..ITERATIONS {
+            (case.expanded)(&mut expanded, n);
+        }
+        let with_seq = fastest(&dir, case.seq);
+        let by_hand = fastest(&dir, &expanded);
+        println!(
+            "{:<22} seq! {:>8.1?}   by hand {:>8.1?}   expansion {:>8.1?}",
+            case.name,
+            with_seq,
+            by_hand,
+            with_seq.saturating_sub(by_hand),
+        );
+    }
+}
+
+fn fastest(dir: &Path, source: &str) -> Duration {
+    (0..RUNS).map(|_| check(dir, source)).min().unwrap()
+}
+
+/// Runs `cargo check` on the crate with `source` as its lib.rs.
+fn check(dir: &Path, source: &str) -> Duration {
+    // A comment that changes every run, so that cargo never skips the check.
+    let stamp = format!("// {:?}\n", Instant::now());
+    fs::write(dir.join("src/lib.rs"), stamp + source).unwrap();
+
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let start = Instant::now();
+    let status = Command::new(cargo)
+        .arg("check")
+        .arg("--quiet")
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    let elapsed = start.elapsed();
+    assert!(status.success(), "cargo check failed in {}", dir.display());
+    elapsed
+}
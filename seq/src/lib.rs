@@ -1,8 +1,1738 @@
 use proc_macro::TokenStream;
+use proc_macro2::{
+    Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream as TokenStream2, TokenTree,
+};
+use std::cell::Cell;
+use std::cmp::Ordering;
+use syn::parse::discouraged::Speculative;
+use syn::parse::{Parse, ParseStream};
+use syn::{
+    braced, bracketed, parenthesized, parse_macro_input, Error, Lit, LitInt, LitStr, Meta, Result,
+    Token,
+};
 
+/// `seq!(N in 0..4 { ... })`: the body, or its `#(...)*` sections, repeated
+/// once for each value of N.
+///
+/// Here and in `#[seq::repeat]` and `#[seq::expand]`, a loop variable that the
+/// body never uses is reported through a call to
+/// `::seq::__unused_loop_variable!`. That path only resolves while this crate
+/// is a dependency named `seq`; under another name in Cargo.toml, start the
+/// variables the body leaves unused with `_` so that no call is emitted.
 #[proc_macro]
 pub fn seq(input: TokenStream) -> TokenStream {
-    let _ = input;
+    let seq = parse_macro_input!(input as Seq);
 
-    unimplemented!()
+    seq.expand()
+        .map(|output| seq.warn_unused(output))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// `seq_array!(N in 0..256 => (N * 7) & 0xff)`: an array literal holding the
+/// value of the expression for each N, as for `#(...)` in a seq! body.
+#[proc_macro]
+pub fn seq_array(input: TokenStream) -> TokenStream {
+    let (vars, expr) = parse_macro_input!(input with parse_mapping);
+    let span = expr
+        .clone()
+        .into_iter()
+        .next()
+        .map_or_else(Span::call_site, |tt| tt.span());
+    let mut expr = Group::new(Delimiter::Parenthesis, expr);
+    expr.set_span(span);
+
+    let item = [
+        TokenTree::Punct(Punct::new('#', Spacing::Alone)),
+        expr.into(),
+    ];
+    let seq = Seq::new(vars, array_of(item.into_iter().collect()));
+
+    seq.expand()
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// `seq_str!(N in 0..4 => "reg~N")`: an array literal of the string with `~N`
+/// pasted in for each N, as for string literals in a seq! body.
+#[proc_macro]
+pub fn seq_str(input: TokenStream) -> TokenStream {
+    let (vars, template) = parse_macro_input!(input with parse_mapping);
+    if let Err(err) = syn::parse2::<LitStr>(template.clone()) {
+        return err.into_compile_error().into();
+    }
+    let seq = Seq::new(vars, array_of(template));
+
+    seq.expand()
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// `N in 0..256 => template`, the input of seq_array! and seq_str!.
+fn parse_mapping(input: ParseStream) -> Result<(Vec<Var>, TokenStream2)> {
+    let vars = parse_vars(input)?;
+    input.parse::<Token![=>]>()?;
+    let template: TokenStream2 = input.parse()?;
+    Ok((vars, template))
+}
+
+/// `[#(item),*]`, the body that repeats `item` into an array literal.
+fn array_of(item: TokenStream2) -> TokenStream2 {
+    let section: [TokenTree; 4] = [
+        Punct::new('#', Spacing::Alone).into(),
+        Group::new(Delimiter::Parenthesis, item).into(),
+        Punct::new(',', Spacing::Alone).into(),
+        Punct::new('*', Spacing::Alone).into(),
+    ];
+    TokenTree::Group(Group::new(
+        Delimiter::Bracket,
+        section.into_iter().collect(),
+    ))
+    .into()
+}
+
+/// `#[seq::repeat(N in 0..8)]` on an item: the item is repeated as if it were
+/// the body of `seq!(N in 0..8 { ... })`.
+#[proc_macro_attribute]
+pub fn repeat(args: TokenStream, item: TokenStream) -> TokenStream {
+    let vars = parse_macro_input!(args with parse_vars);
+    let seq = Seq::new(vars, item.into());
+
+    seq.expand()
+        .map(|output| seq.warn_unused(output))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// `#[seq::expand(N in 0..8)]` on an enum, struct or union: only the variants
+/// or fields marked `#[seq::each]` are repeated.
+#[proc_macro_attribute]
+pub fn expand(args: TokenStream, item: TokenStream) -> TokenStream {
+    let vars = parse_macro_input!(args with parse_vars);
+    let seq = Seq::new(vars, item.into());
+
+    seq.expand_each()
+        .map(|output| seq.warn_unused(output))
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+/// Passes its input through. seq! wraps its expansion in a call to this for
+/// each loop variable that the body never uses, spanned to the variable, since
+/// a deprecation warning is the only warning a proc macro can cause.
+#[doc(hidden)]
+#[deprecated(note = "this loop variable is never used in the body")]
+#[proc_macro]
+pub fn __unused_loop_variable(input: TokenStream) -> TokenStream {
+    input
+}
+
+/// Marks a variant or field to be repeated by `#[seq::expand]`, which removes
+/// it. `#[seq::each(Irq~N)]` also renames each copy.
+#[proc_macro_attribute]
+pub fn each(_args: TokenStream, item: TokenStream) -> TokenStream {
+    let mut output = Error::new(
+        Span::call_site(),
+        "#[seq::each] is only allowed on a variant or field of an item with \
+         #[seq::expand(...)]",
+    )
+    .into_compile_error();
+    output.extend(TokenStream2::from(item));
+    output.into()
+}
+
+/// `N in 0..8 { ... }`, `T in [u8, u16] { ... }`, or with several variables
+/// `R in 0..4, C in 0..4 { ... }`
+struct Seq {
+    vars: Vec<Var>,
+    body: TokenStream2,
+    /// The most tokens the expansion may produce, set by `#![seq(limit = N)]`.
+    limit: usize,
+    /// How many tokens the expansion has produced so far.
+    produced: Cell<usize>,
+}
+
+/// Enough for a 65536-iteration body of about 15 tokens, while stopping an
+/// accidental `0..u32::MAX` before it exhausts the compiler's memory.
+const DEFAULT_LIMIT: usize = 1_000_000;
+
+/// One loop variable and what it iterates over.
+struct Var {
+    ident: Ident,
+    domain: Domain,
+}
+
+enum Domain {
+    Range(Range),
+    /// `[u8, u16, u32]`: each item is substituted as written. The variable's
+    /// value in an [`Env`] is the item's position.
+    List(Vec<TokenStream2>),
+}
+
+impl Var {
+    fn len(&self) -> u128 {
+        match &self.domain {
+            Domain::Range(range) => range.len,
+            Domain::List(items) => items.len() as u128,
+        }
+    }
+
+    fn value(&self, position: u128) -> i128 {
+        match &self.domain {
+            Domain::Range(range) => range.value(position),
+            Domain::List(_) => position as i128,
+        }
+    }
+
+    /// Whether `ident` refers to this variable. A `_` variable is never
+    /// referred to, leaving any `_` in the body alone.
+    fn is(&self, ident: &Ident) -> bool {
+        self.ident != "_" && self.ident == *ident
+    }
+
+    fn is_list(&self) -> bool {
+        matches!(self.domain, Domain::List(_))
+    }
+
+    fn item(&self, n: i128) -> Option<&TokenStream2> {
+        match &self.domain {
+            Domain::Range(_) => None,
+            Domain::List(items) => Some(&items[n as usize]),
+        }
+    }
+}
+
+impl Parse for Seq {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let vars = parse_vars(input)?;
+
+        let content;
+        braced!(content in input);
+        let limit = parse_options(&content)?;
+        let body: TokenStream2 = content.parse()?;
+
+        let mut seq = Seq::new(vars, body);
+        seq.limit = limit;
+        Ok(seq)
+    }
+}
+
+/// The `#![seq(limit = N)]` inner attribute at the start of a body, if any.
+fn parse_options(input: ParseStream) -> Result<usize> {
+    let mut limit = DEFAULT_LIMIT;
+
+    while input.peek(Token![#]) && input.peek2(Token![!]) {
+        let ahead = input.fork();
+        ahead.parse::<Token![#]>()?;
+        ahead.parse::<Token![!]>()?;
+        let content;
+        bracketed!(content in ahead);
+        let meta: Meta = content.parse()?;
+        if !meta.path().is_ident("seq") {
+            break;
+        }
+        input.advance_to(&ahead);
+
+        meta.require_list()?.parse_nested_meta(|meta| {
+            if meta.path.is_ident("limit") {
+                let lit: LitInt = meta.value()?.parse()?;
+                limit = lit.base10_parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported seq! option; expected `limit = N`"))
+            }
+        })?;
+    }
+
+    Ok(limit)
+}
+
+/// The loop variables of a seq! header, up to its body if there is one.
+fn parse_vars(input: ParseStream) -> Result<Vec<Var>> {
+    let mut vars: Vec<Var> = Vec::new();
+
+    loop {
+        let ident = if input.peek(Token![_]) {
+            let underscore: Token![_] = input.parse()?;
+            Ident::new("_", underscore.span)
+        } else {
+            input.parse()?
+        };
+        if vars.iter().any(|var| var.is(&ident)) {
+            return Err(Error::new(
+                ident.span(),
+                format!("loop variable `{}` is declared more than once", ident),
+            ));
+        }
+        input.parse::<Token![in]>()?;
+        let domain = if input.peek(syn::token::Bracket) {
+            Domain::List(parse_list(input)?)
+        } else {
+            Domain::Range(input.parse()?)
+        };
+        vars.push(Var { ident, domain });
+
+        if !input.peek(Token![,]) {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+        if input.is_empty() || input.peek(syn::token::Brace) {
+            break;
+        }
+    }
+
+    Ok(vars)
+}
+
+/// The values taken by the loop variable, in order.
+///
+/// ```text
+/// 0..8
+/// -4..=4
+/// 0u8..4u8
+/// (0..64).step_by(8)
+/// (0..8).rev()
+/// ```
+///
+/// The values are computed as they are needed, so that a range too long to
+/// expand is reported by the expansion limit rather than by running out of
+/// memory.
+struct Range {
+    first: i128,
+    /// The difference between consecutive values, negative once reversed.
+    step: i128,
+    len: u128,
+    /// Type suffix of the bounds, like `u8`, given to every substituted
+    /// literal. Empty if the bounds are unsuffixed.
+    suffix: String,
+}
+
+impl Parse for Range {
+    fn parse(input: ParseStream) -> Result<Self> {
+        if !input.peek(syn::token::Paren) {
+            return parse_bounds(input);
+        }
+
+        let content;
+        parenthesized!(content in input);
+        let mut range = parse_bounds(&content)?;
+
+        // Adapters apply in the order written, as they would on a Rust range:
+        // `(0..8).step_by(3).rev()` is 6, 3, 0 but `(0..8).rev().step_by(3)` is
+        // 7, 4, 1.
+        while input.peek(Token![.]) {
+            input.parse::<Token![.]>()?;
+            let method: Ident = input.parse()?;
+            let args;
+            parenthesized!(args in input);
+            if method == "rev" {
+                range.rev();
+            } else if method == "step_by" {
+                let (step, _, span) = parse_int(&args)?;
+                if step <= 0 {
+                    return Err(Error::new(span, "step must be greater than zero"));
+                }
+                range.step_by(step);
+            } else {
+                return Err(Error::new(
+                    method.span(),
+                    "expected `rev()` or `step_by(...)`",
+                ));
+            }
+            if !args.is_empty() {
+                return Err(args.error("unexpected argument"));
+            }
+        }
+
+        Ok(range)
+    }
+}
+
+impl Range {
+    fn value(&self, position: u128) -> i128 {
+        // Wrapping, as a range may span more than i128::MAX while each of its
+        // values fits.
+        self.first
+            .wrapping_add((position as i128).wrapping_mul(self.step))
+    }
+
+    fn rev(&mut self) {
+        if self.len > 0 {
+            self.first = self.value(self.len - 1);
+            self.step = -self.step;
+        }
+    }
+
+    fn step_by(&mut self, step: i128) {
+        if self.len > 1 {
+            self.len = (self.len - 1) / step as u128 + 1;
+        }
+        // A step past the end leaves a single value, whose step is unused.
+        if self.len > 1 {
+            self.step = self.step.wrapping_mul(step);
+        }
+    }
+}
+
+/// The comma-separated items of `[a, b, c]`. An item with a comma of its own,
+/// like `HashMap<K, V>`, is written in braces, which are removed.
+fn parse_list(input: ParseStream) -> Result<Vec<TokenStream2>> {
+    let content;
+    bracketed!(content in input);
+    let tokens: TokenStream2 = content.parse()?;
+
+    let mut items = vec![TokenStream2::new()];
+    for tt in tokens {
+        match &tt {
+            TokenTree::Punct(comma) if comma.as_char() == ',' => {
+                items.push(TokenStream2::new());
+            }
+            _ => items.last_mut().unwrap().extend([tt]),
+        }
+    }
+    if items.last().unwrap().is_empty() {
+        items.pop();
+    }
+    for item in &mut items {
+        let mut tokens = item.clone().into_iter();
+        if let (Some(TokenTree::Group(group)), None) = (tokens.next(), tokens.next()) {
+            if group.delimiter() == Delimiter::Brace {
+                *item = group.stream();
+            }
+        }
+    }
+    if let Some(empty) = items.iter().position(TokenStream2::is_empty) {
+        return Err(Error::new(
+            content.span(),
+            format!("item {} of the list is empty", empty),
+        ));
+    }
+
+    Ok(items)
+}
+
+fn parse_bounds(input: ParseStream) -> Result<Range> {
+    let (start, start_suffix, start_span) = parse_int(input)?;
+    let inclusive = input.peek(Token![..=]);
+    if inclusive {
+        input.parse::<Token![..=]>()?;
+    } else {
+        input.parse::<Token![..]>()?;
+    }
+    let (end, end_suffix, end_span) = parse_int(input)?;
+
+    let suffix = match (start_suffix.as_str(), end_suffix.as_str()) {
+        (a, b) if a == b => start_suffix,
+        ("", _) => end_suffix,
+        (_, "") => start_suffix,
+        _ => {
+            return Err(Error::new(
+                end_span,
+                format!(
+                    "range bounds have different types, `{}` and `{}`",
+                    start_suffix, end_suffix
+                ),
+            ))
+        }
+    };
+    if suffix.starts_with('u') && start < 0 {
+        return Err(Error::new(
+            start_span,
+            format!("negative bound in a range of `{}`", suffix),
+        ));
+    }
+
+    let len = match (inclusive, start.cmp(&end)) {
+        (_, Ordering::Greater) | (false, Ordering::Equal) => 0,
+        (true, _) => end.abs_diff(start).saturating_add(1),
+        (false, _) => end.abs_diff(start),
+    };
+
+    Ok(Range {
+        first: start,
+        step: 1,
+        len,
+        suffix,
+    })
+}
+
+/// An integer literal, optionally negated, or a block of constant
+/// arithmetic like `{ 4 * 2 }`.
+fn parse_int(input: ParseStream) -> Result<(i128, String, Span)> {
+    let negative = input.peek(Token![-]);
+    if negative {
+        input.parse::<Token![-]>()?;
+    }
+
+    let (value, suffix, span) = if input.peek(syn::token::Brace) {
+        let block: Group = input.parse()?;
+        let value = eval(block.stream(), block.span(), &Env::default())?;
+        (value, String::new(), block.span())
+    } else if input.peek(syn::Ident) {
+        let ident: Ident = input.parse()?;
+        return Err(unresolvable(&ident));
+    } else {
+        let lit: LitInt = input.parse()?;
+        let value = lit.base10_parse::<i128>()?;
+        (value, lit.suffix().to_owned(), lit.span())
+    };
+
+    Ok((if negative { -value } else { value }, suffix, span))
+}
+
+/// The error for a name in a range bound, which seq! has no way to look up.
+fn unresolvable(ident: &Ident) -> Error {
+    Error::new(
+        ident.span(),
+        format!(
+            "seq! cannot evaluate `{}`: macros expand before consts are resolved; \
+             write the value as a literal, or pass it in from a macro_rules! macro \
+             as `$n:literal`",
+            ident,
+        ),
+    )
+}
+
+/// The value of each loop variable in one iteration, and the iteration's
+/// position, counting from zero, which the body reads as `#index`.
+#[derive(Default)]
+struct Env<'a> {
+    vars: Vec<(&'a Var, i128)>,
+    index: usize,
+}
+
+impl Seq {
+    fn new(vars: Vec<Var>, body: TokenStream2) -> Self {
+        Seq {
+            vars,
+            body,
+            limit: DEFAULT_LIMIT,
+            produced: Cell::new(0),
+        }
+    }
+
+    fn expand(&self) -> Result<TokenStream2> {
+        // If the body marks a part of itself with #(...)*, only that part is
+        // repeated. Otherwise the whole body is.
+        if has_section(&self.body) {
+            check_sections(&self.body, &self.vars)?;
+            self.expand_sections(self.body.clone())
+        } else {
+            self.repeat(&self.body, None)
+        }
+    }
+
+    /// Wraps `output` in `::seq::__unused_loop_variable! { ... }` for each
+    /// loop variable that the body does not mention.
+    fn warn_unused(&self, mut output: TokenStream2) -> TokenStream2 {
+        for var in &self.vars {
+            // As with `let`, a leading underscore says the variable is unused
+            // on purpose.
+            if var.ident.to_string().starts_with('_') || mentions(&self.body, &var.ident) {
+                continue;
+            }
+            let path: TokenStream2 = "::seq::__unused_loop_variable!".parse().unwrap();
+            let mut wrapper: TokenStream2 = path
+                .into_iter()
+                .map(|mut tt| {
+                    tt.set_span(var.ident.span());
+                    tt
+                })
+                .collect();
+            wrapper.extend([TokenTree::Group(Group::new(Delimiter::Brace, output))]);
+            output = wrapper;
+        }
+        output
+    }
+
+    /// Substitutes `tokens` once per iteration, with `separator` between
+    /// iterations. With several variables this is their Cartesian product, the
+    /// first variable changing slowest.
+    fn repeat(&self, tokens: &TokenStream2, separator: Option<&TokenTree>) -> Result<TokenStream2> {
+        let total = self
+            .vars
+            .iter()
+            .try_fold(1u128, |total, var| total.checked_mul(var.len()))
+            .unwrap_or(u128::MAX);
+        let template = Template::compile(tokens.clone(), &self.vars)?;
+
+        // Check the size up front rather than finding out after building it.
+        // Every iteration counts as at least one token, so that an empty body
+        // repeated endlessly is caught too.
+        let separators = if separator.is_some() {
+            total.saturating_sub(1)
+        } else {
+            0
+        };
+        let produced = (template.len().max(1) as u128)
+            .saturating_mul(total)
+            .saturating_add(separators)
+            .saturating_add(self.produced.get() as u128);
+        if produced > self.limit as u128 {
+            return Err(Error::new(
+                Span::call_site(),
+                format!(
+                    "seq! expansion would produce {} tokens, more than the limit of {}; \
+                     if this is intended, raise the limit with `#![seq(limit = N)]` at \
+                     the start of the body",
+                    produced, self.limit,
+                ),
+            ));
+        }
+        self.produced.set(produced as usize);
+
+        let mut output = TokenStream2::new();
+        let mut env = Env {
+            vars: Vec::with_capacity(self.vars.len()),
+            index: 0,
+        };
+
+        for iteration in 0..total as usize {
+            env.vars.clear();
+            env.index = iteration;
+            let mut rest = iteration as u128;
+            for var in self.vars.iter().rev() {
+                env.vars.push((var, var.value(rest % var.len())));
+                rest /= var.len();
+            }
+            env.vars.reverse();
+            if iteration > 0 {
+                output.extend(separator.cloned());
+            }
+            template.instantiate(&env, &mut output)?;
+        }
+
+        Ok(output)
+    }
+
+    /// Repeats the variants or fields of the enum, struct or union in the body
+    /// that are marked `#[seq::each]`, leaving the rest of the item as it is.
+    fn expand_each(&self) -> Result<TokenStream2> {
+        let mut tokens: Vec<TokenTree> = self.body.clone().into_iter().collect();
+
+        let keyword = tokens.iter().position(|tt| {
+            matches!(tt, TokenTree::Ident(ident)
+                if ident == "enum" || ident == "struct" || ident == "union")
+        });
+        let fields = keyword.and_then(|keyword| {
+            tokens[keyword..]
+                .iter()
+                .position(|tt| {
+                    matches!(tt, TokenTree::Group(group)
+                    if matches!(group.delimiter(), Delimiter::Brace | Delimiter::Parenthesis))
+                })
+                .map(|offset| keyword + offset)
+        });
+        let fields = match fields {
+            Some(fields) => fields,
+            None => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    "#[seq::expand] expects an enum, or a struct or union with fields",
+                ))
+            }
+        };
+
+        let group = match &tokens[fields] {
+            TokenTree::Group(group) => group.clone(),
+            _ => unreachable!(),
+        };
+        let comma = TokenTree::Punct(Punct::new(',', Spacing::Alone));
+        let mut output = TokenStream2::new();
+
+        for field in split_commas(group.stream()) {
+            match take_each(&field)? {
+                Some(marked) => output.extend(self.repeat(&marked, Some(&comma))?),
+                None => output.extend(field),
+            }
+            output.extend([comma.clone()]);
+        }
+
+        tokens[fields] = respan_group(&group, output);
+        Ok(tokens.into_iter().collect())
+    }
+
+    fn expand_sections(&self, tokens: TokenStream2) -> Result<TokenStream2> {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        let mut output = TokenStream2::new();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            if let Some(section) = section_at(&tokens[i..]) {
+                output.extend(self.repeat(&section.body, section.separator)?);
+                i += section.len;
+                continue;
+            }
+
+            match &tokens[i] {
+                TokenTree::Punct(tilde) if tilde.as_char() == '~' => {
+                    return Err(Error::new(
+                        tilde.span(),
+                        "`~` outside of a `#(...)*` section: only the section is \
+                         repeated, so there is no loop variable to paste here",
+                    ));
+                }
+                // Sections of a nested seq! are its own to repeat.
+                TokenTree::Group(group) if is_nested_seq(&tokens[..i]) => {
+                    output.extend([TokenTree::Group(group.clone())]);
+                }
+                TokenTree::Group(group) => {
+                    let stream = self.expand_sections(group.stream())?;
+                    output.extend([respan_group(group, stream)]);
+                }
+                tt => output.extend([tt.clone()]),
+            }
+            i += 1;
+        }
+
+        Ok(output)
+    }
+}
+
+/// Reports a misplaced `~` or a paste with no loop variable in it, at the
+/// front of `tokens`.
+fn check_syntax(tokens: &[TokenTree], vars: &[Var]) -> Result<()> {
+    match tokens {
+        [TokenTree::Ident(_), ..] if !is_paste(&tokens[1..]) => Ok(()),
+        [TokenTree::Ident(_), rest @ ..] => {
+            let mut len = 0;
+            while is_paste(&rest[len..]) {
+                len += 2;
+            }
+            let segments = rest[..len].iter().skip(1).step_by(2);
+            let has_var = std::iter::once(&tokens[0])
+                .chain(segments)
+                .any(|tt| match tt {
+                    TokenTree::Ident(ident) => vars.iter().any(|var| var.is(ident)),
+                    _ => true,
+                });
+            if has_var {
+                return Ok(());
+            }
+
+            let last = &rest[len - 1];
+            let names: Vec<String> = vars.iter().map(|var| format!("`{}`", var.ident)).collect();
+            let message = match names.as_slice() {
+                [name] => format!(
+                    "`{}` is not a loop variable; the loop variable is {}",
+                    last, name
+                ),
+                _ => format!(
+                    "`{}` is not a loop variable; the loop variables are {}",
+                    last,
+                    names.join(", "),
+                ),
+            };
+            Err(Error::new(last.span(), message))
+        }
+        [TokenTree::Punct(tilde), rest @ ..] if tilde.as_char() == '~' => {
+            // A `~` after an identifier was consumed along with it, unless
+            // nothing valid follows.
+            if !is_paste(tokens) {
+                let span = rest.first().map_or(tilde.span(), TokenTree::span);
+                return Err(Error::new(
+                    span,
+                    "expected a loop variable, `{...}` or `(...)` after `~`",
+                ));
+            }
+            Err(Error::new(
+                tilde.span(),
+                "`~` pastes onto an identifier, as in `Irq~N`",
+            ))
+        }
+        [TokenTree::Punct(pound), TokenTree::Group(group), ..]
+            if pound.as_char() == '#' && group.delimiter() == Delimiter::Brace =>
+        {
+            Err(Error::new(
+                group.span(),
+                "a section is written with parentheses, as in `#(...)*`",
+            ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Rejects a `#(...)` that was meant to be a section but is missing its `*`,
+/// which would otherwise be reported as bad arithmetic.
+fn check_expression(group: &Group) -> Result<()> {
+    if !is_arithmetic(&group.stream()) {
+        return Err(Error::new(
+            group.span(),
+            "expected `*` after `#(...)` to repeat this section; without it, \
+             `#(...)` is an arithmetic expression",
+        ));
+    }
+    Ok(())
+}
+
+/// Checks every section in `tokens` with [`check_section`], before anything
+/// else in the body is reported as being outside of a section.
+fn check_sections(tokens: &TokenStream2, vars: &[Var]) -> Result<()> {
+    let tokens: Vec<TokenTree> = tokens.clone().into_iter().collect();
+
+    for i in 0..tokens.len() {
+        if let Some(section) = section_at(&tokens[i..]) {
+            check_section(&section, &tokens[i..], vars)?;
+        } else if let TokenTree::Group(group) = &tokens[i] {
+            if !is_nested_seq(&tokens[..i]) {
+                check_sections(&group.stream(), vars)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rejects a section that was meant to be arithmetic, as in `#(N + 1) * 4`:
+/// an arithmetic `#(...)*` with no separator, followed by an operand.
+/// `tokens` start at the section.
+fn check_section(section: &Section, tokens: &[TokenTree], vars: &[Var]) -> Result<()> {
+    let operand = match tokens.get(section.len) {
+        Some(tt @ TokenTree::Literal(lit)) if matches!(Lit::new(lit.clone()), Lit::Int(_)) => tt,
+        Some(tt @ TokenTree::Ident(ident)) if vars.iter().any(|var| var.is(ident)) => tt,
+        Some(tt @ TokenTree::Group(group)) if group.delimiter() == Delimiter::Parenthesis => tt,
+        _ => return Ok(()),
+    };
+    if section.separator.is_some() || !is_arithmetic(&section.body) {
+        return Ok(());
+    }
+    let written: TokenStream2 = tokens[..=section.len].iter().cloned().collect();
+    Err(Error::new_spanned(
+        written,
+        format!(
+            "`#(...)*` repeats this section with no separator; to multiply, write \
+             `#(({}) * {})`",
+            section.body, operand,
+        ),
+    ))
+}
+
+/// Whether `tokens` could be an expression for `#(...)`.
+fn is_arithmetic(tokens: &TokenStream2) -> bool {
+    !tokens.clone().into_iter().any(|tt| match tt {
+        TokenTree::Punct(punct) => !"*/%+-<>&^|#".contains(punct.as_char()),
+        TokenTree::Literal(lit) => !matches!(Lit::new(lit), Lit::Int(_)),
+        _ => false,
+    })
+}
+
+/// Whether `tokens` use `name` anywhere, including in pasted identifiers,
+/// expressions and `~N` in string literals.
+fn mentions(tokens: &TokenStream2, name: &Ident) -> bool {
+    tokens.clone().into_iter().any(|tt| match tt {
+        TokenTree::Ident(ident) => ident == *name,
+        TokenTree::Group(group) => mentions(&group.stream(), name),
+        TokenTree::Literal(lit) => {
+            let lit = lit.to_string();
+            lit.contains(&format!("~{}", name)) || lit.contains("~{")
+        }
+        TokenTree::Punct(_) => false,
+    })
+}
+
+/// The comma-separated parts of a variant or field list, without empty ones.
+/// A comma between the `<>` of a field type, as in `Result<u8, u16>`, stays
+/// within its field. After a variant's `=`, `<` and `>` are operators in the
+/// discriminant and are not counted.
+fn split_commas(tokens: TokenStream2) -> Vec<TokenStream2> {
+    let mut parts = vec![TokenStream2::new()];
+    let mut depth = 0usize;
+    let mut discriminant = false;
+    let mut arrow = false;
+    for tt in tokens {
+        if let TokenTree::Punct(punct) = &tt {
+            match punct.as_char() {
+                ',' if depth == 0 => {
+                    parts.push(TokenStream2::new());
+                    discriminant = false;
+                    continue;
+                }
+                '<' if !discriminant => depth += 1,
+                '>' if !discriminant && !arrow => depth = depth.saturating_sub(1),
+                '=' if depth == 0 => discriminant = true,
+                _ => {}
+            }
+            // The `>` of a `fn() -> T` return type closes nothing.
+            arrow = punct.as_char() == '-' && punct.spacing() == Spacing::Joint;
+        } else {
+            arrow = false;
+        }
+        parts.last_mut().unwrap().extend([tt]);
+    }
+    parts.retain(|part| !part.is_empty());
+    parts
+}
+
+/// A variant or field marked `#[seq::each]`, with the marker removed and, for
+/// `#[seq::each(Irq~N)]`, its name replaced by the template to paste.
+fn take_each(field: &TokenStream2) -> Result<Option<TokenStream2>> {
+    let mut tokens: Vec<TokenTree> = field.clone().into_iter().collect();
+
+    let marker = (0..tokens.len()).find_map(|i| match &tokens[i..] {
+        [TokenTree::Punct(pound), TokenTree::Group(attr), ..]
+            if pound.as_char() == '#' && attr.delimiter() == Delimiter::Bracket =>
+        {
+            let path: Vec<String> = attr
+                .stream()
+                .into_iter()
+                .take(4)
+                .map(|tt| tt.to_string())
+                .collect();
+            (path == ["seq", ":", ":", "each"]).then(|| (i, attr.stream()))
+        }
+        _ => None,
+    });
+    let (i, attr) = match marker {
+        Some(marker) => marker,
+        None => return Ok(None),
+    };
+    tokens.drain(i..i + 2);
+
+    let template = match attr.into_iter().nth(4) {
+        None => return Ok(Some(tokens.into_iter().collect())),
+        Some(TokenTree::Group(args)) if args.delimiter() == Delimiter::Parenthesis => args,
+        Some(tt) => return Err(Error::new(tt.span(), "expected `#[seq::each(Name~N)]`")),
+    };
+
+    // The name is the first identifier after the attributes and visibility.
+    let mut name = 0;
+    while name < tokens.len() {
+        match &tokens[name] {
+            TokenTree::Punct(pound) if pound.as_char() == '#' => name += 2,
+            TokenTree::Ident(vis) if vis == "pub" => {
+                name += 1;
+                if matches!(&tokens.get(name), Some(TokenTree::Group(group))
+                    if group.delimiter() == Delimiter::Parenthesis)
+                {
+                    name += 1;
+                }
+            }
+            TokenTree::Ident(_) => break,
+            tt => return Err(Error::new(tt.span(), "expected a variant or field name")),
+        }
+    }
+    if name >= tokens.len() {
+        return Err(Error::new(
+            template.span(),
+            "a renamed #[seq::each] needs a variant or named field",
+        ));
+    }
+
+    tokens.splice(name..name + 1, template.stream());
+    Ok(Some(tokens.into_iter().collect()))
+}
+
+/// The body of a repetition, compiled once into a flat list of operations so
+/// that each iteration is a single pass over it rather than a walk of the
+/// token tree. Groups become `Open`/`Close` pairs.
+struct Template {
+    ops: Vec<Op>,
+}
+
+enum Op {
+    /// Copied unchanged.
+    Token(TokenTree),
+    /// The loop variable at this index in [`Seq::vars`].
+    Var(usize, Span),
+    /// `Irq~N`: the first identifier and the `~segment` tokens after it.
+    Paste(Ident, Vec<TokenTree>),
+    /// A string literal that may contain `~N`, and whether it is inside a
+    /// nested seq!.
+    String(Literal, bool),
+    /// `#(expr)`
+    Expr(Group),
+    /// `#index`
+    Index(Span),
+    Open,
+    Close(Delimiter, Span),
+}
+
+impl Template {
+    fn compile(tokens: TokenStream2, vars: &[Var]) -> Result<Self> {
+        let mut template = Template { ops: Vec::new() };
+        template.push(tokens, vars, false)?;
+        Ok(template)
+    }
+
+    /// Compiles `tokens` onto the end of the template. Inside a nested seq!
+    /// (`nested`) expressions and `#index` are left for the inner invocation,
+    /// which knows the rest of their variables.
+    fn push(&mut self, tokens: TokenStream2, vars: &[Var], nested: bool) -> Result<()> {
+        let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            if !nested {
+                check_syntax(&tokens[i..], vars)?;
+            }
+            if let Some(section) = section_at(&tokens[i..]).filter(|_| !nested) {
+                return Err(Error::new(
+                    section.span,
+                    "`#(...)*` sections cannot be nested; to repeat over another \
+                     variable, use a nested seq!",
+                ));
+            }
+            if let Some(group) = expression_at(&tokens[i..]).filter(|_| !nested) {
+                check_expression(group)?;
+                self.ops.push(Op::Expr(group.clone()));
+                i += 2;
+                continue;
+            }
+            if let Some(ident) = index_at(&tokens[i..]).filter(|_| !nested) {
+                self.ops.push(Op::Index(ident.span()));
+                i += 2;
+                continue;
+            }
+
+            match &tokens[i] {
+                TokenTree::Ident(first) if is_paste(&tokens[i + 1..]) => {
+                    let mut len = 0;
+                    while is_paste(&tokens[i + 1 + len..]) {
+                        len += 2;
+                    }
+                    let rest = &tokens[i + 1..i + 1 + len];
+                    if nested && rest.iter().any(|tt| names_unknown(tt, vars)) {
+                        self.push_partial_paste(first, rest, vars)?;
+                    } else {
+                        self.ops.push(Op::Paste(first.clone(), rest.to_vec()));
+                    }
+                    i += 1 + len;
+                    continue;
+                }
+                TokenTree::Ident(ident) => {
+                    self.ops
+                        .push(match vars.iter().position(|var| var.is(ident)) {
+                            Some(index) => Op::Var(index, ident.span()),
+                            None => Op::Token(TokenTree::Ident(ident.clone())),
+                        });
+                }
+                TokenTree::Group(group) => {
+                    let nested = nested || is_nested_seq(&tokens[..i]);
+                    if nested {
+                        check_shadowing(group, vars)?;
+                    }
+                    self.ops.push(Op::Open);
+                    self.push(group.stream(), vars, nested)?;
+                    self.ops.push(Op::Close(group.delimiter(), group.span()));
+                }
+                TokenTree::Literal(lit) if lit.to_string().contains('~') => {
+                    self.ops.push(Op::String(lit.clone(), nested));
+                }
+                tt => self.ops.push(Op::Token(tt.clone())),
+            }
+            i += 1;
+        }
+
+        Ok(())
+    }
+
+    /// Compiles a paste inside a nested seq! that also uses the inner
+    /// invocation's variables, as in `m~R~_~C`. Only the outer variables are
+    /// substituted, each segment becoming one the inner invocation can paste:
+    /// `m~{0}~_~C`.
+    fn push_partial_paste(
+        &mut self,
+        first: &Ident,
+        rest: &[TokenTree],
+        vars: &[Var],
+    ) -> Result<()> {
+        self.push_segment(first, vars);
+        for pair in rest.chunks(2) {
+            self.ops.push(Op::Token(pair[0].clone()));
+            match &pair[1] {
+                TokenTree::Ident(ident) => self.push_segment(ident, vars),
+                TokenTree::Group(group) => {
+                    self.ops.push(Op::Open);
+                    self.push(group.stream(), vars, true)?;
+                    self.ops.push(Op::Close(group.delimiter(), group.span()));
+                }
+                _ => unreachable!(),
+            }
+        }
+        Ok(())
+    }
+
+    /// One identifier segment of a partial paste. A numeric variable becomes
+    /// `{value}`, since a bare number cannot follow `~`.
+    fn push_segment(&mut self, ident: &Ident, vars: &[Var]) {
+        match vars.iter().position(|var| var.is(ident)) {
+            Some(index) if !vars[index].is_list() => {
+                self.ops.push(Op::Open);
+                self.ops.push(Op::Var(index, ident.span()));
+                self.ops.push(Op::Close(Delimiter::Brace, ident.span()));
+            }
+            Some(_) => self.ops.push(Op::Paste(ident.clone(), Vec::new())),
+            None => self.ops.push(Op::Token(TokenTree::Ident(ident.clone()))),
+        }
+    }
+
+    /// How many tokens one instantiation produces, counting each group as one
+    /// token besides its contents.
+    fn len(&self) -> usize {
+        self.ops
+            .iter()
+            .filter(|op| !matches!(op, Op::Close(..)))
+            .count()
+    }
+
+    /// Appends one iteration of the template to `output`.
+    fn instantiate(&self, env: &Env, output: &mut TokenStream2) -> Result<()> {
+        let mut stack: Vec<Vec<TokenTree>> = Vec::new();
+        let mut current: Vec<TokenTree> = Vec::new();
+
+        for op in &self.ops {
+            let tt = match op {
+                Op::Token(tt) => tt.clone(),
+                Op::Var(index, span) => {
+                    let (var, n) = env.vars[*index];
+                    var_value(var, n, *span)
+                }
+                Op::Paste(first, rest) => TokenTree::Ident(paste(first, rest, env)?.0),
+                Op::String(lit, nested) => paste_into_string(lit, env, *nested)?,
+                Op::Expr(group) => {
+                    let n = eval(group.stream(), group.span(), env)?;
+                    value_literal(n, "", group.span())
+                }
+                Op::Index(span) => value_literal(env.index as i128, "", *span),
+                Op::Open => {
+                    stack.push(std::mem::take(&mut current));
+                    continue;
+                }
+                Op::Close(delimiter, span) => {
+                    let stream = std::mem::replace(&mut current, stack.pop().unwrap());
+                    let mut group = Group::new(*delimiter, stream.into_iter().collect());
+                    group.set_span(*span);
+                    TokenTree::Group(group)
+                }
+            };
+            current.push(tt);
+        }
+
+        output.extend(current);
+        Ok(())
+    }
+}
+
+/// Pastes `first` together with the `~segment` pieces that follow it, as in
+/// `Irq~N`, `m~R~_~C`, `Irq~{N:02}` or `Reg~(N + 1)`. Returns the identifier
+/// and how many tokens after `first` it consumed.
+fn paste(first: &Ident, rest: &[TokenTree], env: &Env) -> Result<(Ident, usize)> {
+    let mut name = segment(first, env)?;
+    let mut len = 0;
+
+    while is_paste(&rest[len..]) {
+        match &rest[len + 1] {
+            TokenTree::Ident(ident) => name.push_str(&segment(ident, env)?),
+            TokenTree::Group(group) => {
+                let (n, format) = match group.delimiter() {
+                    Delimiter::Brace => formatted(group, env)?,
+                    _ => (
+                        eval(group.stream(), group.span(), env)?,
+                        PasteFormat::default(),
+                    ),
+                };
+                if n < 0 {
+                    return Err(Error::new(
+                        group.span(),
+                        format!("cannot paste negative value {} into an identifier", n),
+                    ));
+                }
+                name.push_str(&format.apply(n));
+            }
+            _ => unreachable!(),
+        }
+        len += 2;
+    }
+
+    // A list item such as `1.5`, `'c'` or `r#type` pastes text that need not
+    // make an identifier. Anything but word characters is turned away before
+    // parsing, as the compiler's lexer reports a name like `fr#type` itself.
+    let bare = name.strip_prefix("r#").unwrap_or(&name);
+    let ident = if bare.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        syn::parse_str::<Ident>(&name).ok()
+    } else {
+        None
+    };
+    let Some(mut ident) = ident else {
+        return Err(Error::new(
+            first.span(),
+            format!("`{}` is not a valid identifier", name),
+        ));
+    };
+    ident.set_span(first.span());
+    Ok((ident, len))
+}
+
+/// The text that one piece of a pasted identifier contributes.
+fn segment(ident: &Ident, env: &Env) -> Result<String> {
+    match lookup(env, ident) {
+        Some((var, n)) if var.is_list() => {
+            let item = var.item(n).unwrap();
+            match item.clone().into_iter().collect::<Vec<_>>().as_slice() {
+                [TokenTree::Ident(_) | TokenTree::Literal(_)] => Ok(item.to_string()),
+                _ => Err(Error::new(
+                    ident.span(),
+                    format!("cannot paste `{}` into an identifier", item),
+                )),
+            }
+        }
+        Some((_, n)) if n < 0 => Err(Error::new(
+            ident.span(),
+            format!("cannot paste negative value {} into an identifier", n),
+        )),
+        Some((_, n)) => Ok(n.to_string()),
+        None => Ok(ident.to_string()),
+    }
+}
+
+/// The value and format of a `{N:02x}` or `{N + 1:02}` paste segment.
+fn formatted(group: &Group, env: &Env) -> Result<(i128, PasteFormat)> {
+    let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+    let colon = tokens
+        .iter()
+        .rposition(|tt| matches!(tt, TokenTree::Punct(punct) if punct.as_char() == ':'));
+    let (expr, spec) = match colon {
+        Some(colon) => (&tokens[..colon], Some(&tokens[colon + 1..])),
+        None => (&tokens[..], None),
+    };
+    let n = eval(expr.iter().cloned().collect(), group.span(), env)?;
+
+    let format = match spec {
+        None => PasteFormat::default(),
+        Some(spec) => {
+            let spec: String = spec.iter().map(ToString::to_string).collect();
+            PasteFormat::parse(&spec).ok_or_else(|| {
+                Error::new(
+                    group.span(),
+                    format!(
+                        "unsupported format `{}`; expected a zero-padded width and/or a \
+                         radix, like `02`, `x` or `04X`",
+                        spec,
+                    ),
+                )
+            })?
+        }
+    };
+
+    Ok((n, format))
+}
+
+/// How a value is written when pasted: `{N:02}`, `{N:x}`, `{N:04X}`, `{N:08b}`.
+#[derive(Default)]
+struct PasteFormat {
+    width: usize,
+    radix: Option<char>,
+}
+
+impl PasteFormat {
+    fn parse(spec: &str) -> Option<Self> {
+        let (digits, radix) = match spec.chars().last() {
+            Some(radix @ ('x' | 'X' | 'o' | 'b')) => (&spec[..spec.len() - 1], Some(radix)),
+            _ => (spec, None),
+        };
+
+        // Identifiers cannot contain spaces, so only zero padding is allowed.
+        let width = match digits {
+            "" => 0,
+            _ if digits.starts_with('0') => digits.parse().ok()?,
+            _ => return None,
+        };
+
+        Some(PasteFormat { width, radix })
+    }
+
+    fn apply(&self, n: i128) -> String {
+        let digits = match self.radix {
+            Some('x') => format!("{:x}", n.unsigned_abs()),
+            Some('X') => format!("{:X}", n.unsigned_abs()),
+            Some('o') => format!("{:o}", n.unsigned_abs()),
+            Some('b') => format!("{:b}", n.unsigned_abs()),
+            _ => n.unsigned_abs().to_string(),
+        };
+        let sign = if n < 0 { "-" } else { "" };
+        format!("{}{:0>width$}", sign, digits, width = self.width)
+    }
+}
+
+/// Substitutes `~N` and `~{expr:spec}` inside a string literal, as in
+/// `#[doc = "IRQ ~N"]` or `#[link_name = "irq_~{N + 1:02}"]`. A `~` that is
+/// not followed by a loop variable is left as it is. Inside a nested seq!, an
+/// expression that also uses the inner invocation's variables only has the
+/// loop variables substituted, `~{R + C}` becoming `~{0 + C}`.
+fn paste_into_string(lit: &Literal, env: &Env, nested: bool) -> Result<TokenTree> {
+    let lit_span = lit.span();
+    let value = match Lit::new(lit.clone()) {
+        Lit::Str(s) if s.value().contains('~') => s.value(),
+        _ => return Ok(TokenTree::Literal(lit.clone())),
+    };
+
+    let mut output = String::new();
+    let mut rest = value.as_str();
+
+    while let Some(tilde) = rest.find('~') {
+        output.push_str(&rest[..tilde]);
+        rest = &rest[tilde + 1..];
+
+        let (name, spec, len) = match rest.strip_prefix('{').zip(rest.find('}')) {
+            Some((inner, close)) => {
+                let (name, spec) = inner[..close - 1]
+                    .rsplit_once(':')
+                    .unwrap_or((&inner[..close - 1], ""));
+                (name.trim(), Some(spec.trim()), close + 1)
+            }
+            None => {
+                let len = rest
+                    .find(|c: char| !c.is_alphanumeric() && c != '_')
+                    .unwrap_or(rest.len());
+                (&rest[..len], None, len)
+            }
+        };
+
+        let n = if name.is_empty() || syn::parse_str::<Ident>(name).is_ok() {
+            match env
+                .vars
+                .iter()
+                .find(|(var, _)| name != "_" && var.ident == name)
+            {
+                Some(&(var, n)) if var.is_list() => match spec {
+                    None | Some("") => {
+                        output.push_str(&var.item(n).unwrap().to_string());
+                        rest = &rest[len..];
+                        continue;
+                    }
+                    Some(_) => {
+                        return Err(Error::new(
+                            lit_span,
+                            format!("`{}` iterates over a list and cannot be formatted", name),
+                        ))
+                    }
+                },
+                Some(&(_, n)) => n,
+                None => {
+                    output.push('~');
+                    continue;
+                }
+            }
+        } else {
+            let in_paste =
+                |err: Error| Error::new(lit_span, format!("in `~{{{}}}`: {}", name, err));
+            let expr: TokenStream2 = name.parse().map_err(|err| in_paste(Error::from(err)))?;
+            if nested && mentions_unknown(expr.clone(), env) {
+                output.push_str(&format!("~{{{}", substitute(expr, env)));
+                if let Some(spec) = spec.filter(|spec| !spec.is_empty()) {
+                    output.push_str(&format!(":{}", spec));
+                }
+                output.push('}');
+                rest = &rest[len..];
+                continue;
+            }
+            eval(expr, lit_span, env).map_err(in_paste)?
+        };
+        let format = match spec {
+            Some(spec) => PasteFormat::parse(spec).ok_or_else(|| {
+                Error::new(
+                    lit_span,
+                    format!(
+                        "unsupported format `{}` for `~{{{}}}` in string",
+                        spec, name
+                    ),
+                )
+            })?,
+            None => PasteFormat::default(),
+        };
+
+        output.push_str(&format.apply(n));
+        rest = &rest[len..];
+    }
+    output.push_str(rest);
+
+    let mut lit = Literal::string(&output);
+    lit.set_span(lit_span);
+    Ok(TokenTree::Literal(lit))
+}
+
+/// Whether `tokens` use an identifier other than the loop variables.
+fn mentions_unknown(tokens: TokenStream2, env: &Env) -> bool {
+    tokens.into_iter().any(|tt| match tt {
+        TokenTree::Ident(ident) => lookup(env, &ident).is_none(),
+        TokenTree::Group(group) => mentions_unknown(group.stream(), env),
+        _ => false,
+    })
+}
+
+/// `tokens` with the loop variables replaced by their values.
+fn substitute(tokens: TokenStream2, env: &Env) -> TokenStream2 {
+    tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Ident(ident) => match lookup(env, &ident) {
+                Some((var, n)) => var_value(var, n, ident.span()),
+                None => TokenTree::Ident(ident),
+            },
+            TokenTree::Group(group) => {
+                let mut substituted =
+                    Group::new(group.delimiter(), substitute(group.stream(), env));
+                substituted.set_span(group.span());
+                TokenTree::Group(substituted)
+            }
+            tt => tt,
+        })
+        .collect()
+}
+
+fn lookup<'a>(env: &Env<'a>, ident: &Ident) -> Option<(&'a Var, i128)> {
+    env.vars
+        .iter()
+        .find(|(var, _)| var.is(ident))
+        .map(|&(var, n)| (var, n))
+}
+
+/// The literal substituted for a loop variable or `#(...)` expression,
+/// carrying the range's type suffix, if any, and the span of the tokens it
+/// replaces.
+fn value_literal(n: i128, suffix: &str, span: Span) -> TokenTree {
+    let mut lit = if suffix.is_empty() {
+        Literal::i128_unsuffixed(n)
+    } else {
+        format!("{}{}", n, suffix).parse().unwrap()
+    };
+    lit.set_span(span);
+
+    // Keep a negative value together as one operand, so that `N.abs()` means
+    // what it would for a variable. The parentheses are spanned to the macro
+    // call rather than to the caller's code, which keeps them out of the
+    // `unused_parens` lint.
+    if n < 0 {
+        TokenTree::Group(Group::new(
+            Delimiter::Parenthesis,
+            TokenTree::Literal(lit).into(),
+        ))
+    } else {
+        TokenTree::Literal(lit)
+    }
+}
+
+/// The tokens substituted for a loop variable.
+fn var_value(var: &Var, n: i128, span: Span) -> TokenTree {
+    match &var.domain {
+        Domain::Range(range) => value_literal(n, &range.suffix, span),
+        Domain::List(items) => list_item(&items[n as usize]),
+    }
+}
+
+/// The tokens substituted for a list variable. An item of several tokens,
+/// like `Vec<u8>`, is kept together in an invisible group.
+fn list_item(item: &TokenStream2) -> TokenTree {
+    let mut tokens = item.clone().into_iter();
+    match (tokens.next(), tokens.next()) {
+        (Some(tt), None) => tt,
+        _ => TokenTree::Group(Group::new(Delimiter::None, item.clone())),
+    }
+}
+
+/// Whether `tokens` continue an identifier with `~segment`.
+fn is_paste(tokens: &[TokenTree]) -> bool {
+    match tokens {
+        [TokenTree::Punct(tilde), TokenTree::Ident(_), ..] => tilde.as_char() == '~',
+        [TokenTree::Punct(tilde), TokenTree::Group(group), ..] => {
+            tilde.as_char() == '~'
+                && matches!(group.delimiter(), Delimiter::Brace | Delimiter::Parenthesis)
+        }
+        _ => false,
+    }
+}
+
+/// Whether a paste segment mentions an identifier other than the loop
+/// variables, such as a variable of a nested seq!.
+fn names_unknown(tt: &TokenTree, vars: &[Var]) -> bool {
+    match tt {
+        TokenTree::Ident(ident) => !vars.iter().any(|var| var.is(ident)),
+        TokenTree::Group(group) => group
+            .stream()
+            .into_iter()
+            .any(|tt| names_unknown(&tt, vars)),
+        _ => false,
+    }
+}
+
+/// Whether the tokens before a group end in `seq!`, making the group the
+/// input of a nested invocation.
+fn is_nested_seq(before: &[TokenTree]) -> bool {
+    match before {
+        [.., TokenTree::Ident(name), TokenTree::Punct(bang)] => {
+            name == "seq" && bang.as_char() == '!'
+        }
+        _ => false,
+    }
+}
+
+/// Rejects a nested seq! that declares a variable of the same name as an
+/// enclosing one, which would otherwise have its header substituted away.
+fn check_shadowing(group: &Group, vars: &[Var]) -> Result<()> {
+    let header = group
+        .stream()
+        .into_iter()
+        .take_while(|tt| !matches!(tt, TokenTree::Group(g) if g.delimiter() == Delimiter::Brace))
+        .collect::<Vec<_>>();
+
+    for pair in header.windows(2) {
+        if let [TokenTree::Ident(ident), TokenTree::Ident(keyword)] = pair {
+            if keyword == "in" && vars.iter().any(|var| var.is(ident)) {
+                return Err(Error::new(
+                    ident.span(),
+                    format!(
+                        "loop variable `{}` is already declared by an enclosing seq!",
+                        ident,
+                    ),
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The contents of a `#(...)*` section starting at the front of `tokens`.
+fn section_at(tokens: &[TokenTree]) -> Option<Section<'_>> {
+    let group = match tokens {
+        [TokenTree::Punct(pound), TokenTree::Group(group), ..]
+            if pound.as_char() == '#' && group.delimiter() == Delimiter::Parenthesis =>
+        {
+            group
+        }
+        _ => return None,
+    };
+    let is_star = |tt: &TokenTree| matches!(tt, TokenTree::Punct(star) if star.as_char() == '*');
+
+    let separator = match &tokens[2..] {
+        // `#(...)**` separates with `*`, as in macro_rules!.
+        [separator, star, ..] if is_star(star) && !matches!(separator, TokenTree::Group(_)) => {
+            Some(separator)
+        }
+        [star, ..] if is_star(star) => None,
+        _ => return None,
+    };
+
+    Some(Section {
+        span: group.span(),
+        body: group.stream(),
+        separator,
+        len: if separator.is_some() { 4 } else { 3 },
+    })
+}
+
+/// A `#(...)*` section, or `#(...)sep*` with one token between iterations.
+struct Section<'a> {
+    span: Span,
+    body: TokenStream2,
+    separator: Option<&'a TokenTree>,
+    /// How many tokens the section takes up.
+    len: usize,
+}
+
+/// The expression of a `#(...)` at the front of `tokens` that is not a
+/// section, as in `#(N * 4)`.
+fn expression_at(tokens: &[TokenTree]) -> Option<&Group> {
+    match tokens {
+        [TokenTree::Punct(pound), TokenTree::Group(group), ..]
+            if pound.as_char() == '#'
+                && group.delimiter() == Delimiter::Parenthesis
+                && section_at(tokens).is_none() =>
+        {
+            Some(group)
+        }
+        _ => None,
+    }
+}
+
+/// The `index` of a `#index` at the front of `tokens`.
+fn index_at(tokens: &[TokenTree]) -> Option<&Ident> {
+    match tokens {
+        [TokenTree::Punct(pound), TokenTree::Ident(index), ..]
+            if pound.as_char() == '#' && index == "index" =>
+        {
+            Some(index)
+        }
+        _ => None,
+    }
+}
+
+fn has_section(tokens: &TokenStream2) -> bool {
+    let tokens: Vec<TokenTree> = tokens.clone().into_iter().collect();
+
+    (0..tokens.len()).any(|i| {
+        section_at(&tokens[i..]).is_some()
+            || matches!(&tokens[i], TokenTree::Group(group)
+                if !is_nested_seq(&tokens[..i]) && has_section(&group.stream()))
+    })
+}
+
+fn respan_group(original: &Group, stream: TokenStream2) -> TokenTree {
+    let mut group = Group::new(original.delimiter(), stream);
+    group.set_span(original.span());
+    TokenTree::Group(group)
+}
+
+/// Evaluates integer arithmetic at expansion time: literals, loop variables
+/// from `env`, parentheses, unary `-`, and the binary operators
+/// `* / % + - << >> & ^ |` with Rust's precedence.
+fn eval(tokens: TokenStream2, span: Span, env: &Env) -> Result<i128> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut eval = Eval {
+        tokens: &tokens,
+        pos: 0,
+        span,
+        env,
+    };
+    let value = eval.binary(0)?;
+    match eval.tokens.get(eval.pos) {
+        None => Ok(value),
+        Some(tt) => Err(Error::new(tt.span(), "expected an arithmetic operator")),
+    }
+}
+
+struct Eval<'a> {
+    tokens: &'a [TokenTree],
+    pos: usize,
+    /// Where to report an expression that ends too early.
+    span: Span,
+    env: &'a Env<'a>,
+}
+
+#[derive(Clone, Copy)]
+enum BinOp {
+    Mul,
+    Div,
+    Rem,
+    Add,
+    Sub,
+    Shl,
+    Shr,
+    BitAnd,
+    BitXor,
+    BitOr,
+}
+
+impl BinOp {
+    fn precedence(self) -> u8 {
+        match self {
+            BinOp::Mul | BinOp::Div | BinOp::Rem => 5,
+            BinOp::Add | BinOp::Sub => 4,
+            BinOp::Shl | BinOp::Shr => 3,
+            BinOp::BitAnd => 2,
+            BinOp::BitXor => 1,
+            BinOp::BitOr => 0,
+        }
+    }
+
+    fn apply(self, lhs: i128, rhs: i128) -> Option<i128> {
+        match self {
+            BinOp::Mul => lhs.checked_mul(rhs),
+            BinOp::Div => lhs.checked_div(rhs),
+            BinOp::Rem => lhs.checked_rem(rhs),
+            BinOp::Add => lhs.checked_add(rhs),
+            BinOp::Sub => lhs.checked_sub(rhs),
+            BinOp::Shl => u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shl(rhs)),
+            BinOp::Shr => u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shr(rhs)),
+            BinOp::BitAnd => Some(lhs & rhs),
+            BinOp::BitXor => Some(lhs ^ rhs),
+            BinOp::BitOr => Some(lhs | rhs),
+        }
+    }
+}
+
+impl<'a> Eval<'a> {
+    /// Parses operators binding at least as tightly as `min_precedence`.
+    fn binary(&mut self, min_precedence: u8) -> Result<i128> {
+        let mut lhs = self.unary()?;
+
+        while let Some((op, len)) = self.peek_op() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            let span = self.tokens[self.pos].span();
+            self.pos += len;
+            let rhs = self.binary(op.precedence() + 1)?;
+            lhs = op
+                .apply(lhs, rhs)
+                .ok_or_else(|| Error::new(span, "arithmetic overflow or division by zero"))?;
+        }
+
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<i128> {
+        match self.tokens.get(self.pos) {
+            Some(TokenTree::Punct(punct)) if punct.as_char() == '-' => {
+                self.pos += 1;
+                let span = punct.span();
+                self.unary()?
+                    .checked_neg()
+                    .ok_or_else(|| Error::new(span, "arithmetic overflow"))
+            }
+            _ => self.operand(),
+        }
+    }
+
+    fn operand(&mut self) -> Result<i128> {
+        let tt = match self.tokens.get(self.pos) {
+            Some(tt) => tt,
+            None => return Err(Error::new(self.span, "expected an integer")),
+        };
+        self.pos += 1;
+
+        match tt {
+            TokenTree::Literal(lit) => {
+                let lit = LitInt::from(lit.clone());
+                lit.base10_parse::<i128>()
+            }
+            TokenTree::Ident(ident) => match lookup(self.env, ident) {
+                Some((var, _)) if var.is_list() => Err(Error::new(
+                    ident.span(),
+                    format!("`{}` iterates over a list and has no numeric value", ident),
+                )),
+                Some((_, n)) => Ok(n),
+                None => Err(unresolvable(ident)),
+            },
+            TokenTree::Punct(pound) if pound.as_char() == '#' => match self.tokens.get(self.pos) {
+                Some(TokenTree::Ident(index)) if index == "index" => {
+                    self.pos += 1;
+                    Ok(self.env.index as i128)
+                }
+                _ => Err(Error::new(pound.span(), "expected `#index`")),
+            },
+            TokenTree::Group(group)
+                if matches!(group.delimiter(), Delimiter::Parenthesis | Delimiter::None) =>
+            {
+                eval(group.stream(), group.span(), self.env)
+            }
+            _ => Err(Error::new(tt.span(), "expected an integer")),
+        }
+    }
+
+    fn peek_op(&self) -> Option<(BinOp, usize)> {
+        let punct = |i: usize| match self.tokens.get(self.pos + i) {
+            Some(TokenTree::Punct(punct)) => Some(punct),
+            _ => None,
+        };
+
+        let first = punct(0)?;
+        let doubled = |ch: char| {
+            first.spacing() == Spacing::Joint && punct(1).map(|p| p.as_char()) == Some(ch)
+        };
+
+        let op = match first.as_char() {
+            '*' => (BinOp::Mul, 1),
+            '/' => (BinOp::Div, 1),
+            '%' => (BinOp::Rem, 1),
+            '+' => (BinOp::Add, 1),
+            '-' => (BinOp::Sub, 1),
+            '<' if doubled('<') => (BinOp::Shl, 2),
+            '>' if doubled('>') => (BinOp::Shr, 2),
+            '&' => (BinOp::BitAnd, 1),
+            '^' => (BinOp::BitXor, 1),
+            '|' => (BinOp::BitOr, 1),
+            _ => return None,
+        };
+        Some(op)
+    }
 }
@@ -0,0 +1,57 @@
+// Besides plain `a..b` and `a..=b`, the range in the header may be stepped,
+// reversed, or negative, written the same way as the equivalent Rust range:
+//
+//     seq!(N in (0..64).step_by(8) { ... })
+//     seq!(N in (0..8).rev() { ... })
+//     seq!(N in -4..4 { ... })
+//
+// Adapters apply in the order written, so `(0..8).step_by(3).rev()` gives 6,
+// 3, 0 while `(0..8).rev().step_by(3)` gives 7, 4, 1.
+//
+// If the bounds carry a type suffix like `0u8..4u8`, every substituted literal
+// carries it too, so the values keep the declared type.
+
+use seq::seq;
+
+fn type_name<T>(_: T) -> &'static str {
+    std::any::type_name::<T>()
+}
+
+fn main() {
+    let stepped = seq!(N in (0..64).step_by(8) { [#(N,)*] });
+    assert_eq!(stepped, [0, 8, 16, 24, 32, 40, 48, 56]);
+
+    let reversed = seq!(N in (0..8).rev() { [#(N,)*] });
+    assert_eq!(reversed, [7, 6, 5, 4, 3, 2, 1, 0]);
+
+    let step_then_rev = seq!(N in (0..8).step_by(3).rev() { [#(N,)*] });
+    assert_eq!(step_then_rev, [6, 3, 0]);
+
+    let rev_then_step = seq!(N in (0..=8).rev().step_by(3) { [#(N,)*] });
+    assert_eq!(rev_then_step, [8, 5, 2]);
+
+    let signed = seq!(N in -4..4 { [#(N,)*] });
+    assert_eq!(signed, [-4, -3, -2, -1, 0, 1, 2, 3]);
+
+    // A negative value stays one operand.
+    let abs = seq!(N in -2i32..=-1 { [#(N.abs(),)*] });
+    assert_eq!(abs, [2, 1]);
+
+    let typed = seq!(N in 0u8..4u8 { [#(type_name(N),)*] });
+    assert_eq!(typed, ["u8"; 4]);
+
+    let typed = seq!(N in (-2..2i16).rev() { [#(N,)*] });
+    assert_eq!(type_name(typed), "[i16; 4]");
+    assert_eq!(typed, [1, 0, -1, -2]);
+
+    let untyped = seq!(N in 0..1 { type_name(N) });
+    assert_eq!(untyped, "i32");
+}
+
+// The parentheses around a negative value must not trip the lint in the
+// caller's code.
+#[deny(unused_parens)]
+fn _negative_binding() -> i32 {
+    let x = seq!(N in -1..0 { N });
+    x
+}
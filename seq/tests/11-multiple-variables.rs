@@ -0,0 +1,63 @@
+// Several loop variables may be declared in one header, separated by commas.
+// The body is then repeated for every combination of their values, the first
+// variable changing slowest:
+//
+//     seq!(R in 0..2, C in 0..3 { fn m~R~_~C() {} })
+//
+// expands to m0_0, m0_1, m0_2, m1_0, m1_1, m1_2.
+//
+// A seq! nested inside the body may also use the outer variables, since they
+// are substituted before the inner invocation expands. Its #(...)* sections
+// belong to the inner invocation and are left alone by the outer one, and so
+// do the parts of a paste that name the inner variables: in `m~R~_~C` below
+// the outer seq! substitutes R only, and the inner one pastes the rest. The
+// same goes for `~{R + C}` in a string literal.
+
+use seq::seq;
+
+struct Matrix([[u32; 3]; 2]);
+
+impl Matrix {
+    seq!(R in 0..2, C in 0..3 {
+        fn m~R~_~C(&self) -> u32 {
+            self.0[R][C]
+        }
+    });
+}
+
+struct Grid([[u32; 2]; 2]);
+
+impl Grid {
+    seq!(R in 0..2 {
+        seq!(C in 0..2 {
+            fn m~R~_~C(&self) -> u32 {
+                self.0[R][C]
+            }
+
+            const NAME~R~_~C: &str = "m~{R}_~C: ~{R + C:02}";
+        });
+    });
+}
+
+seq!(R in 0..3 {
+    seq!(C in 0..R {
+        const LOWER~R: &[(usize, usize)] = &[#((R, C),)*];
+    });
+});
+
+fn main() {
+    let m = Matrix([[1, 2, 3], [4, 5, 6]]);
+    assert_eq!(m.m0_0() + m.m0_2() + m.m1_1(), 1 + 3 + 5);
+
+    let g = Grid([[1, 2], [3, 4]]);
+    assert_eq!(g.m0_0() + g.m0_1() + g.m1_0() + g.m1_1(), 10);
+    assert_eq!(Grid::NAME1_0, "m1_0: 01");
+    assert_eq!(Grid::NAME1_1, "m1_1: 02");
+
+    let pairs = seq!(R in 0..2, C in 0..2 { [#((R, C),)*] });
+    assert_eq!(pairs, [(0, 0), (0, 1), (1, 0), (1, 1)]);
+
+    assert_eq!(LOWER0, &[]);
+    assert_eq!(LOWER1, &[(1, 0)]);
+    assert_eq!(LOWER2, &[(2, 0), (2, 1)]);
+}
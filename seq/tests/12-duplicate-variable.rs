@@ -0,0 +1,18 @@
+// Two loop variables with the same name would make every use of that name
+// ambiguous, whether both are declared in one header or one in a nested seq!
+// that would see its own variable replaced by the outer value. Both cases are
+// rejected, pointing at the second declaration.
+
+use seq::seq;
+
+seq!(N in 0..2, N in 0..2 {
+    fn f~N() {}
+});
+
+seq!(N in 0..2 {
+    seq!(N in 0..2 {
+        fn g~N() {}
+    });
+});
+
+fn main() {}
@@ -0,0 +1,26 @@
+// A range bound may be a block of constant arithmetic, evaluated while the
+// macro expands:
+//
+//     seq!(N in 0..{ 4 * 2 } { ... })
+//
+// The block supports integer literals, parentheses, unary minus and the
+// operators `* / % + - << >> & ^ |` with the same precedence as in Rust.
+
+use seq::seq;
+
+fn main() {
+    let eight = seq!(N in 0..{ 4 * 2 } { [#(N,)*] });
+    assert_eq!(eight, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+    let shifted = seq!(N in { 1 << 2 }..={ (1 << 3) - 1 } { [#(N,)*] });
+    assert_eq!(shifted, [4, 5, 6, 7]);
+
+    let precedence = seq!(N in { 2 + 3 * 4 }..{ 2 + 3 * 4 + 1 } { N });
+    assert_eq!(precedence, 14);
+
+    let negative = seq!(N in -{ 6 / 3 }..{ 7 % 3 } { [#(N,)*] });
+    assert_eq!(negative, [-2, -1, 0]);
+
+    let stepped = seq!(N in (0..{ 0xff & 0x0f | 0x10 }).step_by({ 2 * 4 }) { [#(N,)*] });
+    assert_eq!(stepped, [0, 8, 16, 24]);
+}
@@ -0,0 +1,18 @@
+// Macros expand before names are resolved, so there is no way for seq! to
+// find out the value of a const named in its header. Rather than failing to
+// parse, it explains why and suggests a workaround: keeping the value in a
+// macro_rules! macro, as in 09-interaction-with-macrorules.rs.
+
+use seq::seq;
+
+const NPROC: usize = 4;
+
+seq!(N in 0..NPROC {
+    fn f~N() {}
+});
+
+seq!(N in 0..{ NPROC * 2 } {
+    fn g~N() {}
+});
+
+fn main() {}
@@ -0,0 +1,50 @@
+// A pasted value can be formatted by writing the variable in braces with a
+// format spec, so that names sort and line up the way hardware manuals write
+// them:
+//
+//     seq!(N in 0..16 { struct Irq~{N:02}; })  // Irq00, Irq01, ..., Irq15
+//     seq!(N in 0..16 { struct Reg~{N:x}; })   // Reg0, ..., Rega, ..., Regf
+//
+// The spec is an optional zero-padded width followed by an optional radix
+// `x`, `X`, `o` or `b`. Identifiers cannot contain spaces, so a width without
+// the leading `0` is rejected. The braces are required: an unbraced
+// `Irq~N:02` would be ambiguous with a struct field initializer `x~N: 02`.
+//
+// The same `~N` and `~{N:spec}` forms are substituted inside string literals,
+// which is handy for `#[doc]` and `#[link_name]`. A `~` in a string that is not
+// followed by a loop variable is left alone.
+
+use seq::seq;
+
+seq!(N in 8..11 {
+    #[derive(Debug, PartialEq)]
+    struct Irq~{N:02};
+});
+
+seq!(N in 10..12 {
+    #[doc = "Register 0x~{N:x} at offset ~N."]
+    struct Reg~{N:X};
+
+    impl Reg~{N:X} {
+        const NAME: &'static str = "reg_~{N:04b}";
+        const HOME: &'static str = "~/regs/~{N}";
+    }
+});
+
+fn main() {
+    assert_eq!(Irq08, Irq08);
+    assert_eq!(Irq09, Irq09);
+    assert_eq!(Irq10, Irq10);
+
+    assert_eq!(RegA::NAME, "reg_1010");
+    assert_eq!(RegB::NAME, "reg_1011");
+    assert_eq!(RegA::HOME, "~/regs/10");
+
+    seq!(N in 0..3 {
+        let bits = [#(stringify!(Bit~{N:03b}),)*];
+    });
+    assert_eq!(bits, ["Bit000", "Bit001", "Bit010"]);
+
+    let hex = seq!(N in 254..=255 { [#(stringify!(Byte~{N:02x}),)*] });
+    assert_eq!(hex, ["Bytefe", "Byteff"]);
+}
@@ -0,0 +1,44 @@
+// Inside the body, `#(expr)` evaluates arithmetic on the loop variables while
+// the macro expands and becomes an integer literal spanned to the expression:
+//
+//     seq!(N in 0..4 { const OFFSET~N: usize = #(N * 4); })
+//
+// The expression supports the same operators as a range bound. A parenthesized
+// expression can also be pasted into an identifier, as in `Reg~(N + 1)`, and
+// the braced paste form takes an expression before its format spec, as in
+// `Reg~{N + 1:02}` or `"reg_~{N * 4:x}"`.
+//
+// A `#(...)` that is followed by `*` is still a repeated section, so a product
+// is written `#((N + 1) * 4)` rather than `#(N + 1) * 4`. Expressions
+// inside a nested seq! are left for the inner invocation, so they may mention
+// its variables too.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    const OFFSET~N: usize = #(N * 4);
+    struct Reg~(N + 1);
+    struct Port~{N + 8:02};
+
+    impl Reg~(N + 1) {
+        const LINK: &'static str = "reg_~{N * 4:x}";
+    }
+});
+
+fn main() {
+    assert_eq!([OFFSET0, OFFSET1, OFFSET2, OFFSET3], [0, 4, 8, 12]);
+    let _ = (Reg1, Reg2, Reg3, Reg4);
+    let _ = (Port08, Port09, Port10, Port11);
+    assert_eq!(Reg4::LINK, "reg_c");
+
+    let masks = seq!(N in 0..4 { [#((1 << N) - 1,)*] });
+    assert_eq!(masks, [0, 1, 3, 7]);
+
+    let squares = seq!(N in -2..=2 { [#(#(N * N),)*] });
+    assert_eq!(squares, [4, 1, 0, 1, 4]);
+
+    let grid = seq!(R in 0..2 {
+        [#(seq!(C in 0..3 { [#(#(R * 3 + C),)*] }),)*]
+    });
+    assert_eq!(grid, [[0, 1, 2], [3, 4, 5]]);
+}
@@ -0,0 +1,55 @@
+// Besides an integer range, a loop variable can iterate over a bracketed list
+// of items, each substituted exactly as written:
+//
+//     seq!(T in [u8, u16, u32, u64] {
+//         impl Width for T {
+//             const BITS: u32 = T::BITS;
+//         }
+//     });
+//
+// An item that contains a comma of its own, like `HashMap<K, V>`, goes in
+// braces, which are removed. Items that are a single identifier or literal can
+// be pasted into identifiers and strings like integers can.
+//
+// Inside the body, `#index` is the position of the current iteration counting
+// from zero, and can also be used in `#(...)` arithmetic. With several loop
+// variables it counts through all of their combinations.
+
+use seq::seq;
+use std::collections::HashMap;
+
+trait Width {
+    const INDEX: usize;
+    const NAME: &'static str;
+}
+
+seq!(T in [u8, u16, u32, u64] {
+    impl Width for T {
+        const INDEX: usize = #index;
+        const NAME: &'static str = "~T";
+    }
+});
+
+seq!(T in [Alpha, Beta, Gamma,] {
+    #[derive(Debug)]
+    struct Marker~T;
+});
+
+fn main() {
+    assert_eq!(<u8 as Width>::INDEX, 0);
+    assert_eq!(<u64 as Width>::INDEX, 3);
+    assert_eq!(<u32 as Width>::NAME, "u32");
+
+    let _ = (MarkerAlpha, MarkerBeta, MarkerGamma);
+
+    let sizes = seq!(T in [u8, {HashMap<u8, u16>}, [u16; 3]] {
+        [#(core::mem::size_of::<T>(),)*]
+    });
+    assert_eq!(sizes, [1, core::mem::size_of::<HashMap<u8, u16>>(), 6]);
+
+    let offsets = seq!(T in [u8, u16, u32] { [#((#index * 4, T::BITS),)*] });
+    assert_eq!(offsets, [(0, 8), (4, 16), (8, 32)]);
+
+    let pairs = seq!(T in [a, b], N in 1..=2 { [#((#index, stringify!(T~N)),)*] });
+    assert_eq!(pairs, [(0, "a1"), (1, "a2"), (2, "b1"), (3, "b2")]);
+}
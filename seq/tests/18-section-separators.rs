@@ -0,0 +1,45 @@
+// A section can put a separator token between its repetitions, written between
+// the closing parenthesis and the `*` as in macro_rules!:
+//
+//     seq!(N in 0..3 { #(a~N),* })    // a0, a1, a2
+//     seq!(N in 0..3 { #(a~N)+* })    // a0 + a1 + a2
+//
+// so there is no trailing separator to work around. The separator is a single
+// token: a punctuation character, identifier or literal.
+//
+// A body may contain any number of sections. Each is repeated over the same
+// loop variables, independently of the others.
+
+use seq::seq;
+
+seq!(N in 0..3 {
+    type Tuple = (#(T~N),*);
+
+    fn sum(#(a~N: u32),*) -> u32 {
+        #(a~N)+*
+    }
+
+    fn product(#(a~N: u32),*) -> u32 {
+        #(a~N)**
+    }
+});
+
+seq!(N in 0..3 {
+    struct T~N;
+});
+
+seq!(N in 0..3 {
+    fn bits() -> u32 {
+        #(1 << N)|*
+    }
+});
+
+fn main() {
+    let _: Tuple = (T0, T1, T2);
+    assert_eq!(sum(1, 2, 3), 6);
+    assert_eq!(product(2, 3, 4), 24);
+    assert_eq!(bits(), 0b111);
+
+    let nested = seq!(R in 0..2 { [#(seq!(C in 0..3 { [#(C + R * 3),*] })),*] });
+    assert_eq!(nested, [[0, 1, 2], [3, 4, 5]]);
+}
@@ -0,0 +1,67 @@
+// seq! is also available as attributes, so that the repeated code stays
+// ordinary Rust that rustfmt and IDEs understand.
+//
+// `#[seq::repeat(N in 0..8)]` repeats the item it is placed on, with the same
+// header and substitution as `seq!(N in 0..8 { ... })`.
+//
+// `#[seq::expand(N in 0..8)]` goes on an enum, struct or union and repeats only
+// the variants or fields marked `#[seq::each]`. Because the compiler parses the
+// item before the attribute runs, a pasted name like `Irq~N` cannot be written
+// in the item itself; instead the marker takes it as an argument,
+// `#[seq::each(Irq~N)] Irq`, and each copy is renamed. Pasting into string
+// literals works as in seq!, and field types may have generic arguments.
+
+trait Register {
+    const OFFSET: usize;
+}
+
+struct Reg<const N: usize>;
+
+#[seq::repeat(N in 0..4)]
+impl Register for Reg<N> {
+    const OFFSET: usize = N * 4;
+}
+
+#[seq::expand(N in 0..3)]
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Interrupt {
+    Reset,
+    #[seq::each(Line~N)]
+    /// External interrupt line ~N.
+    Line,
+    Fault = 1 << 4,
+}
+
+#[seq::expand(N in 1..=2)]
+#[derive(Default)]
+struct Bank {
+    status: u8,
+    #[seq::each(data~N)]
+    pub data: u16,
+    #[seq::each(last~N)]
+    last: Option<Result<u8, fn(u8) -> u16>>,
+}
+
+#[seq::expand(N in 1..=3)]
+struct Arrays(u8, #[seq::each] [u8; N]);
+
+fn main() {
+    assert_eq!(<Reg<3> as Register>::OFFSET, 12);
+
+    let all = [
+        Interrupt::Reset,
+        Interrupt::Line0,
+        Interrupt::Line1,
+        Interrupt::Line2,
+        Interrupt::Fault,
+    ];
+    assert_eq!(all[2], Interrupt::Line1);
+    assert_eq!(Interrupt::Fault as u8, 16);
+
+    let bank = Bank::default();
+    let _: (u8, u16, u16) = (bank.status, bank.data1, bank.data2);
+    assert!(bank.last1.is_none() && bank.last2.is_none());
+
+    let arrays = Arrays(0, [1], [1, 2], [1, 2, 3]);
+    assert_eq!(arrays.3.len(), 3);
+}
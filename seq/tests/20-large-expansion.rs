@@ -0,0 +1,24 @@
+// seq! compiles its body once into a flat list of operations and replays it
+// for each iteration, so large expansions cost time proportional to the number
+// of tokens produced, without walking the body's token tree again each time.
+//
+// This test expands 10,000 iterations of a body with a nested group, a paste
+// and an expression, checking that the result is right. How long expansions
+// of this size take is measured by `cargo bench --bench expansion`.
+
+use seq::seq;
+
+seq!(N in 0..10000 {
+    static TABLE: [(u32, u32); 10000] = [#((N, #(N * 3 % 7)),)*];
+});
+
+mod items {
+    seq::seq!(N in 0..2000 {
+        pub const C~N: usize = #(N * 4);
+    });
+}
+
+fn main() {
+    assert_eq!(TABLE[9999], (9999, 9999 * 3 % 7));
+    assert_eq!(items::C1999, 1999 * 4);
+}
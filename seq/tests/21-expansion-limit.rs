@@ -0,0 +1,31 @@
+// To keep a typo like `0..1000000` from exhausting the compiler's memory, an
+// expansion is limited to 1,000,000 tokens, checked before any of it is built.
+// An expansion that really needs more raises the limit with an inner attribute
+// at the start of the body. A range is never stored value by value, so even
+// `0..u32::MAX` is reported here rather than running out of memory, and each
+// iteration counts as at least one token so that an empty body is no way
+// around the limit:
+//
+//     seq!(N in 0..65536 {
+//         #![seq(limit = 2_000_000)]
+//         ...
+//     })
+
+use seq::seq;
+
+seq!(N in 0..65536 {
+    #![seq(limit = 2_000_000)]
+    static WIDE: [u32; 65536] = [#(N,)*];
+});
+
+seq!(N in 0..1000000 {
+    static HUGE: [u32; 1000000] = [#(N,)*];
+});
+
+seq!(N in 0..4294967295 {
+    const _: u32 = N;
+});
+
+seq!(N in 0..4294967295 {});
+
+fn main() {}
@@ -0,0 +1,40 @@
+// Lookup tables are arrays of one expression evaluated for each N. Rather than
+// spelling out `[#(#(expr),)*]` in a seq! body, seq_array! takes the
+// expression directly and produces the array literal:
+//
+//     const TABLE: [u8; 256] = seq_array!(N in 0..256 => (N * 7) & 0xff);
+//
+// The expression supports the same operators as `#(...)` in a seq! body. The
+// elements are unsuffixed integer literals, so their type comes from where the
+// array is used, and a value that does not fit is reported by the compiler.
+//
+// seq_str! does the same for a string literal with `~N` pasted in:
+//
+//     const NAMES: [&str; 4] = seq_str!(N in 0..4 => "reg~{N:02}");
+
+use seq::{seq_array, seq_str};
+
+const SCRAMBLE: [u8; 256] = seq_array!(N in 0..256 => (N * 7) & 0xff);
+
+const POPCOUNT: [u8; 256] = seq_array!(N in 0..256 =>
+    (N & 1) + (N >> 1 & 1) + (N >> 2 & 1) + (N >> 3 & 1)
+        + (N >> 4 & 1) + (N >> 5 & 1) + (N >> 6 & 1) + (N >> 7 & 1)
+);
+
+const NAMES: [&str; 4] = seq_str!(N in 0..4 => "reg~{N:02}");
+
+fn main() {
+    for n in 0..256 {
+        assert_eq!(SCRAMBLE[n], (n * 7 & 0xff) as u8);
+        assert_eq!(POPCOUNT[n], (n as u8).count_ones() as u8);
+    }
+
+    assert_eq!(NAMES, ["reg00", "reg01", "reg02", "reg03"]);
+
+    let grid: [(u8, u8); 6] = seq_array!(R in 0..2, C in 0..3 => R * 3 + C)
+        .map(|i: u8| (i / 3, i % 3));
+    assert_eq!(grid[4], (1, 1));
+
+    let types = seq_str!(T in [u8, u16] => "~T");
+    assert_eq!(types, ["u8", "u16"]);
+}
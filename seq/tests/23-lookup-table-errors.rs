@@ -0,0 +1,8 @@
+// An expression that cannot be evaluated for some N is reported at the
+// operator that failed, just as for `#(...)` in a seq! body.
+
+use seq::seq_array;
+
+const INVERSES: [i32; 5] = seq_array!(N in 0..5 => 60 / (N - 2));
+
+fn main() {}
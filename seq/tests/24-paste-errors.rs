@@ -0,0 +1,37 @@
+// Mistakes in a pasted identifier are reported where they were made, rather
+// than as a puzzling syntax error in the expanded code:
+//
+//   - a paste with no loop variable in it, such as `Irq~M` when the variable
+//     is `N`, names the loop variables;
+//   - a `~` with nothing to paste after it points at what follows;
+//   - a `~` with no identifier before it points at the `~`;
+//   - a list item that does not make an identifier, such as `1.5` or
+//     `r#type`, points at the paste.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    struct Irq~M;
+});
+
+seq!(R in 0..2, C in 0..2 {
+    struct Cell~X~Y;
+});
+
+seq!(N in 0..4 {
+    struct Irq~;
+});
+
+seq!(N in 0..4 {
+    const VALUE: u32 = ~N;
+});
+
+seq!(T in [1.5, b] {
+    fn f~T() {}
+});
+
+seq!(T in [r#type] {
+    fn f~T() {}
+});
+
+fn main() {}
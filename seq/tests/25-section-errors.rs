@@ -0,0 +1,35 @@
+// Malformed `#(...)*` sections are reported at the section:
+//
+//   - without its `*`, `#(...)` is an arithmetic expression, so one that is not
+//     arithmetic is most likely a section missing its `*`;
+//   - sections do not nest, since each repeats over all the loop variables;
+//   - a section is written with parentheses, not braces;
+//   - once a body has a section, only the section is repeated, so a paste
+//     outside of it has no value to paste;
+//   - `#(N + 1) * 4` reads as a section without a separator followed by `4`,
+//     so the error suggests the parentheses that make it a product.
+
+use seq::seq;
+
+seq!(N in 0..4 {
+    static A: [u32; 4] = [#(N,)];
+});
+
+seq!(N in 0..4 {
+    static B: [[u32; 4]; 4] = [#([#(N,)*],)*];
+});
+
+seq!(N in 0..4 {
+    static C: [u32; 4] = [#{N,}*];
+});
+
+seq!(N in 0..4 {
+    struct Irq~N;
+    static D: [u32; 4] = [#(N,)*];
+});
+
+seq!(N in 0..4 {
+    const E~N: usize = #(N + 1) * 4;
+});
+
+fn main() {}
@@ -0,0 +1,41 @@
+// A loop variable that the body never uses is usually a typo or a leftover.
+// Procedural macros cannot emit warnings of their own, so seq! routes the
+// expansion through a deprecated helper macro, spanned to the variable, which
+// makes the compiler warn about it. This test denies the warning so that it
+// shows up as an error.
+//
+// As with `let`, a variable named `_`, or whose name starts with an
+// underscore, is unused on purpose and not warned about, so a body can simply
+// be repeated N times. A `_` in the body is never substituted.
+//
+// The expansion is otherwise unchanged and still works in any position, here
+// as the tail expression of a function.
+
+#![deny(deprecated)]
+
+use seq::seq;
+
+fn three() -> u32 {
+    seq!(N in 0..1 { 3 })
+}
+
+seq!(R in 0..2, C in 0..2 {
+    #(const _: u32 = R;)*
+});
+
+fn twice_four() -> u32 {
+    let mut x = 0;
+    seq!(_ in 0..4 {
+        let _ = x;
+        x += 1;
+    });
+    seq!(_N in 0..4 {
+        x += 1;
+    });
+    x
+}
+
+fn main() {
+    assert_eq!(three(), 3);
+    assert_eq!(twice_four(), 8);
+}
@@ -1,13 +1,30 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse-header.rs");
-    //t.pass("tests/02-parse-body.rs");
-    //t.compile_fail("tests/03-expand-four-errors.rs");
-    //t.pass("tests/04-paste-ident.rs");
-    //t.pass("tests/05-repeat-section.rs");
-    //t.pass("tests/06-init-array.rs");
-    //t.pass("tests/07-inclusive-range.rs");
-    //t.compile_fail("tests/08-ident-span.rs");
-    //t.pass("tests/09-interaction-with-macrorules.rs");
+    t.pass("tests/01-parse-header.rs");
+    t.pass("tests/02-parse-body.rs");
+    t.compile_fail("tests/03-expand-four-errors.rs");
+    t.pass("tests/04-paste-ident.rs");
+    t.pass("tests/05-repeat-section.rs");
+    t.pass("tests/06-init-array.rs");
+    t.pass("tests/07-inclusive-range.rs");
+    t.compile_fail("tests/08-ident-span.rs");
+    t.pass("tests/09-interaction-with-macrorules.rs");
+    t.pass("tests/10-range-forms.rs");
+    t.pass("tests/11-multiple-variables.rs");
+    t.compile_fail("tests/12-duplicate-variable.rs");
+    t.pass("tests/13-const-bounds.rs");
+    t.compile_fail("tests/14-named-const-bound.rs");
+    t.pass("tests/15-formatted-paste.rs");
+    t.pass("tests/16-body-arithmetic.rs");
+    t.pass("tests/17-list-variables.rs");
+    t.pass("tests/18-section-separators.rs");
+    t.pass("tests/19-attribute-forms.rs");
+    t.pass("tests/20-large-expansion.rs");
+    t.compile_fail("tests/21-expansion-limit.rs");
+    t.pass("tests/22-lookup-tables.rs");
+    t.compile_fail("tests/23-lookup-table-errors.rs");
+    t.compile_fail("tests/24-paste-errors.rs");
+    t.compile_fail("tests/25-section-errors.rs");
+    t.compile_fail("tests/26-unused-variable.rs");
 }
@@ -0,0 +1,679 @@
+//! Rewrites the items and match expressions marked `#[sorted]` in Rust source
+//! files into sorted order.
+//!
+//!     $ cargo run -p sorted --bin sorted-fix -- [--check] FILE...
+//!
+//! Each variant, field, arm or item moves together with its attributes and doc
+//! comments, and, when written on a line of its own, with any `//` comment
+//! lines directly above it and a `//` comment at the end of its line. With
+//! `--check` nothing is written; a diff of the changes is printed instead and
+//! the exit status is 1 if there are any.
+
+#[path = "../order.rs"]
+#[allow(dead_code)] // Parts of the module are only used by the macros.
+mod order;
+
+use crate::order::{
+    discriminant, is_group_marker, item_ident, pattern_entry, use_tree_entry, Entry, Order,
+};
+use proc_macro2::{LineColumn, Span};
+use std::cmp::Ordering;
+use std::env;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use syn::spanned::Spanned;
+use syn::visit::{self, Visit};
+use syn::{
+    Arm, Attribute, Expr, ExprMatch, Fields, ImplItem, ItemEnum, ItemImpl, ItemMod, ItemStruct,
+    ItemUse, Meta, Pat, UseTree,
+};
+
+/// Lines of unchanged context around the changes in a diff.
+const CONTEXT: usize = 3;
+
+fn main() -> ExitCode {
+    let mut check = false;
+    let mut paths = Vec::new();
+    for arg in env::args_os().skip(1) {
+        if arg == "--check" {
+            check = true;
+        } else {
+            paths.push(PathBuf::from(arg));
+        }
+    }
+    if paths.is_empty() {
+        eprintln!("usage: sorted-fix [--check] FILE...");
+        return ExitCode::from(2);
+    }
+
+    let mut status = 0;
+    for path in &paths {
+        match run(path, check) {
+            Ok(true) if check => status = status.max(1),
+            Ok(_) => {}
+            Err(err) => {
+                eprintln!("sorted-fix: {}: {}", path.display(), err);
+                status = 2;
+            }
+        }
+    }
+    ExitCode::from(status)
+}
+
+/// Sorts one file, returning whether anything was out of order.
+fn run(path: &Path, check: bool) -> Result<bool, String> {
+    let source = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let fixed = fix(&source, |message| {
+        eprintln!("sorted-fix: {}: {}", path.display(), message);
+    })
+    .map_err(|err| {
+        let start = err.span().start();
+        format!("{}:{}: {}", start.line, start.column + 1, err)
+    })?;
+
+    if fixed == source {
+        return Ok(false);
+    }
+    if check {
+        print_diff(path, &source, &fixed);
+    } else {
+        fs::write(path, &fixed).map_err(|err| err.to_string())?;
+        eprintln!("sorted-fix: sorted {}", path.display());
+    }
+    Ok(true)
+}
+
+/// Returns `source` with every `#[sorted]` list in order. Lists nested in
+/// another one, such as a sorted match inside a sorted module, are sorted
+/// first and the file parsed again before the outer list is moved around.
+fn fix(source: &str, mut skip: impl FnMut(String)) -> syn::Result<String> {
+    let mut source = source.to_owned();
+
+    loop {
+        let file = syn::parse_file(&source)?;
+        let mut fixer = Fixer {
+            source: &source,
+            line_starts: line_starts(&source),
+            edits: Vec::new(),
+            skipped: Vec::new(),
+        };
+        fixer.visit_file(&file);
+
+        let Fixer { edits, skipped, .. } = fixer;
+        if edits.is_empty() {
+            skipped.into_iter().for_each(&mut skip);
+            return Ok(source);
+        }
+
+        // Innermost first, leaving any list that contains one of them for the
+        // next round.
+        let mut edits = edits;
+        edits.sort_by_key(|edit| edit.range.len());
+        let mut chosen: Vec<Edit> = Vec::new();
+        for edit in edits {
+            if chosen.iter().all(|other| {
+                edit.range.end <= other.range.start || other.range.end <= edit.range.start
+            }) {
+                chosen.push(edit);
+            }
+        }
+
+        chosen.sort_by_key(|edit| std::cmp::Reverse(edit.range.start));
+        for edit in chosen {
+            source.replace_range(edit.range, &edit.replacement);
+        }
+    }
+}
+
+/// Replaces a range of the source with the same elements in sorted order.
+struct Edit {
+    range: Range<usize>,
+    replacement: String,
+}
+
+/// One of the things a `#[sorted]` list puts in order.
+struct Element<'a> {
+    entry: Entry<'a>,
+    /// The whole variant, field, arm or item, attributes included.
+    span: Span,
+    /// Elements with a lower rank go first whatever their names: `self` in a
+    /// use group has rank 0 and a wildcard arm rank 2.
+    rank: u8,
+    /// Text to add after the element when it moves, for a match arm that
+    /// needs a comma but was written last without one.
+    suffix: &'static str,
+    /// The element's `#[sorted::group]` attribute.
+    marker: Option<Span>,
+}
+
+impl<'a> Element<'a> {
+    fn new(entry: Entry<'a>, span: Span) -> Self {
+        Element {
+            entry,
+            span,
+            rank: 1,
+            suffix: "",
+            marker: None,
+        }
+    }
+
+    /// Notes the `#[sorted::group]` marker among `attrs`, if there is one.
+    fn with_attrs(mut self, attrs: &[Attribute]) -> Self {
+        self.entry = self.entry.with_attrs(attrs);
+        self.marker = attrs
+            .iter()
+            .find(|attr| is_group_marker(attr))
+            .map(Spanned::span);
+        self
+    }
+}
+
+struct Fixer<'a> {
+    source: &'a str,
+    line_starts: Vec<usize>,
+    edits: Vec<Edit>,
+    /// Lists left alone, and why.
+    skipped: Vec<String>,
+}
+
+impl Fixer<'_> {
+    /// Sorts `elements`, given in source order, within each group started by
+    /// `#[sorted::group]`, and with `groups_sorted` the groups themselves.
+    fn sort(&mut self, elements: Vec<Element>, order: &Order) {
+        if !elements.iter().any(|element| element.entry.starts_group) {
+            return self.reorder(elements, order);
+        }
+
+        let mut groups: Vec<Vec<Element>> = Vec::new();
+        for element in elements {
+            match groups.last_mut() {
+                Some(group) if !element.entry.starts_group => group.push(element),
+                _ => groups.push(vec![element]),
+            }
+        }
+
+        let mut heads = Vec::new();
+        for group in groups {
+            let (first, last) = (&group[0], &group[group.len() - 1]);
+            heads.push(Element {
+                rank: group.iter().map(|element| element.rank).max().unwrap(),
+                suffix: last.suffix,
+                marker: None,
+                ..Element::new(
+                    Entry {
+                        starts_group: first.entry.starts_group,
+                        ..Entry::new(first.entry.name.clone(), first.entry.tokens)
+                    },
+                    first.span.join(last.span).unwrap_or(first.span),
+                )
+            });
+            self.reorder(group, order);
+        }
+
+        // The elements above the first marker have no marker to move along,
+        // so they have to stay first.
+        if order.groups_sorted {
+            if !heads[0].entry.starts_group
+                && heads[1..]
+                    .iter()
+                    .any(|head| element_cmp(order, head, &heads[0]).is_lt())
+            {
+                return self.skip(
+                    heads[0].span,
+                    "cannot move the elements above the first #[sorted::group]",
+                );
+            }
+            self.reorder(heads, order);
+        }
+    }
+
+    /// Adds an edit putting `elements`, given in source order, in sorted
+    /// order, if they are not already. A `#[sorted::group]` marker on the
+    /// first element moves to whichever element comes first instead.
+    fn reorder(&mut self, elements: Vec<Element>, order: &Order) {
+        let mut sorted: Vec<usize> = (0..elements.len()).collect();
+        sorted.sort_by(|&a, &b| element_cmp(order, &elements[a], &elements[b]));
+        if sorted.iter().enumerate().all(|(i, &j)| i == j) {
+            return;
+        }
+
+        let mut ranges: Vec<Range<usize>> = elements
+            .iter()
+            .map(|element| self.offset(element.span.start())..self.offset(element.span.end()))
+            .collect();
+        // How far each element's text runs past its last token.
+        let mut tails = vec![0; ranges.len()];
+        let mut commas = vec![false; ranges.len()];
+        // Elements written one per line take their indentation, any comment
+        // lines directly above them and the rest of their line along: the
+        // separating comma and a trailing `//` comment.
+        if ranges.iter().all(|range| self.starts_line(range.start)) {
+            let line_ends: Option<Vec<(usize, bool)>> = ranges
+                .iter()
+                .map(|range| self.rest_of_line(range.end))
+                .collect();
+            for (i, range) in ranges.iter_mut().enumerate() {
+                range.start = self.extend_over_comments(range.start);
+                if let Some(line_ends) = &line_ends {
+                    (tails[i], commas[i]) = (line_ends[i].0 - range.end, line_ends[i].1);
+                    range.end = line_ends[i].0;
+                }
+            }
+        }
+        let comma_separated = commas.contains(&true);
+
+        let mut texts: Vec<String> = ranges
+            .iter()
+            .map(|range| self.source[range.clone()].to_owned())
+            .collect();
+        if let Some(marker) = elements[0].marker.filter(|_| sorted[0] != 0) {
+            // The marker along with the space up to the next token.
+            let start = self.offset(marker.start());
+            let end = self.offset(marker.end());
+            let end = end + self.source[end..].len() - self.source[end..].trim_start().len();
+            let marker = &self.source[start..end];
+
+            texts[0].replace_range(start - ranges[0].start..end - ranges[0].start, "");
+            let first = sorted[0];
+            let at = self.offset(elements[first].span.start()) - ranges[first].start;
+            texts[first].insert_str(at, marker);
+        }
+
+        let mut replacement = String::new();
+        for (slot, &i) in sorted.iter().enumerate() {
+            let mut text = std::mem::take(&mut texts[i]);
+            if slot + 1 < ranges.len() {
+                // An element that was last may lack the separator it now needs.
+                let suffix = match elements[i].suffix {
+                    "" if comma_separated && !commas[i] => ",",
+                    suffix => suffix,
+                };
+                text.insert_str(text.len() - tails[i], suffix);
+            }
+            replacement += &text;
+            if slot + 1 < ranges.len() {
+                replacement += &self.source[ranges[slot].end..ranges[slot + 1].start];
+            }
+        }
+
+        self.edits.push(Edit {
+            range: ranges[0].start..ranges[ranges.len() - 1].end,
+            replacement,
+        });
+    }
+
+    /// The end of the line that `offset`, the end of an element, is on, if
+    /// the rest of the line is no more than a comma and a `//` comment, and
+    /// whether there is a comma.
+    fn rest_of_line(&self, offset: usize) -> Option<(usize, bool)> {
+        let line_end = self.source[offset..]
+            .find('\n')
+            .map_or(self.source.len(), |i| offset + i);
+        let rest = self.source[offset..line_end].trim_start();
+        let (rest, comma) = match rest.strip_prefix(',') {
+            Some(rest) => (rest.trim_start(), true),
+            None => (rest, false),
+        };
+        let rest = rest.trim_end();
+        if rest.is_empty() || rest.starts_with("//") {
+            Some((line_end, comma))
+        } else {
+            None
+        }
+    }
+
+    fn starts_line(&self, offset: usize) -> bool {
+        let line_start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        self.source[line_start..offset].trim().is_empty()
+    }
+
+    /// Moves `offset`, the start of an element that starts its line, back to
+    /// the start of that line and over the `//` comment lines above it.
+    fn extend_over_comments(&self, offset: usize) -> usize {
+        let mut start = self.source[..offset].rfind('\n').map_or(0, |i| i + 1);
+        while start > 0 {
+            let above = self.source[..start - 1].rfind('\n').map_or(0, |i| i + 1);
+            if !self.source[above..start].trim_start().starts_with("//") {
+                break;
+            }
+            start = above;
+        }
+        start
+    }
+
+    fn offset(&self, position: LineColumn) -> usize {
+        let line_start = self.line_starts[position.line - 1];
+        self.source[line_start..]
+            .char_indices()
+            .nth(position.column)
+            .map_or(self.source.len(), |(i, _)| line_start + i)
+    }
+
+    fn skip(&mut self, span: Span, reason: &str) {
+        let start = span.start();
+        self.skipped
+            .push(format!("{}:{}: {}", start.line, start.column + 1, reason));
+    }
+
+    /// Sorts the alternatives of each `A | B` pattern, then the arms by their
+    /// first alternative, keeping a catch-all arm last. Moving an arm past a
+    /// catch-all changes which one runs, so a match with a catch-all before its
+    /// last arm is left alone.
+    fn sort_arms(&mut self, arms: &[Arm], order: &Order) {
+        let mut elements = Vec::new();
+        let mut bounds = Vec::new();
+
+        for (i, arm) in arms.iter().enumerate() {
+            if is_catch_all(&arm.pat) && i + 1 < arms.len() {
+                return self.skip(arm.span(), "cannot sort arms after a catch-all arm");
+            }
+            let cases: Vec<&Pat> = match &arm.pat {
+                pat if is_catch_all(pat) => {
+                    let entry = Entry::new("_".to_owned(), pat);
+                    elements.push(Element {
+                        rank: 2,
+                        ..Element::new(entry, arm.span()).with_attrs(&arm.attrs)
+                    });
+                    continue;
+                }
+                Pat::Or(pat) => pat.cases.iter().collect(),
+                pat => vec![pat],
+            };
+
+            let mut entries = Vec::new();
+            for case in &cases {
+                match pattern_entry(case) {
+                    Ok(entry) => entries.push(Element::new(entry, case.span())),
+                    Err(err) => return self.skip(case.span(), &err.to_string()),
+                }
+            }
+            let first = entries
+                .iter()
+                .min_by(|a, b| order.cmp(&a.entry, &b.entry))
+                .unwrap();
+            let last = entries
+                .iter()
+                .max_by(|a, b| order.cmp(&a.entry, &b.entry))
+                .unwrap();
+            bounds.push((
+                Entry::new(first.entry.name.clone(), &arm.pat),
+                Entry::new(last.entry.name.clone(), &arm.pat),
+            ));
+
+            let entry = Entry::new(first.entry.name.clone(), &arm.pat);
+            let mut element = Element::new(entry, arm.span()).with_attrs(&arm.attrs);
+            if arm.comma.is_none() && !is_block_like(&arm.body) {
+                element.suffix = ",";
+            }
+            elements.push(element);
+            if entries.len() > 1 {
+                self.sort(entries, order);
+            }
+        }
+
+        // `A | C => ..., B => ...` cannot be put in order by moving whole arms.
+        for (i, (first, last)) in bounds.iter().enumerate() {
+            for (other_first, other_last) in &bounds[i + 1..] {
+                if order.cmp(first, other_last).is_le() && order.cmp(other_first, last).is_le() {
+                    return self.skip(
+                        arms[0].span(),
+                        "cannot sort arms whose alternatives interleave",
+                    );
+                }
+            }
+        }
+
+        self.sort(elements, order);
+    }
+
+    fn sort_use_tree(&mut self, tree: &UseTree, order: &Order) {
+        match tree {
+            UseTree::Path(path) => self.sort_use_tree(&path.tree, order),
+            UseTree::Group(group) => {
+                let mut elements = Vec::new();
+                for tree in &group.items {
+                    let mut element = Element::new(use_tree_entry(tree), tree.span());
+                    if matches!(tree, UseTree::Name(name) if name.ident == "self") {
+                        element.rank = 0;
+                    }
+                    elements.push(element);
+                    self.sort_use_tree(tree, order);
+                }
+                self.sort(elements, order);
+            }
+            UseTree::Name(_) | UseTree::Rename(_) | UseTree::Glob(_) => {}
+        }
+    }
+}
+
+impl<'ast> Visit<'ast> for Fixer<'_> {
+    fn visit_item_enum(&mut self, node: &'ast ItemEnum) {
+        if let Some(order) = sorted_order(&node.attrs) {
+            if !order.by_discriminant {
+                let elements = node
+                    .variants
+                    .iter()
+                    .map(|variant| {
+                        let entry = Entry::new(variant.ident.to_string(), &variant.ident);
+                        Element::new(entry, variant.span()).with_attrs(&variant.attrs)
+                    })
+                    .collect();
+                self.sort(elements, &order);
+            } else if node.variants.iter().all(|v| v.discriminant.is_some()) {
+                let mut elements = Vec::new();
+                for variant in &node.variants {
+                    let (_, expr) = variant.discriminant.as_ref().unwrap();
+                    let Ok(value) = discriminant(expr) else {
+                        return self.skip(expr.span(), "discriminant is not an integer literal");
+                    };
+                    let mut entry = Entry::new(variant.ident.to_string(), &variant.ident);
+                    entry.discriminant = value;
+                    elements.push(Element::new(entry, variant.span()).with_attrs(&variant.attrs));
+                }
+                self.sort(elements, &order);
+            } else {
+                // Moving a variant would change the implicit discriminants.
+                self.skip(
+                    node.ident.span(),
+                    "cannot reorder an enum with implicit discriminants",
+                );
+            }
+        }
+        visit::visit_item_enum(self, node);
+    }
+
+    fn visit_item_struct(&mut self, node: &'ast ItemStruct) {
+        if let (Some(order), Fields::Named(fields)) = (sorted_order(&node.attrs), &node.fields) {
+            let elements = fields
+                .named
+                .iter()
+                .filter_map(|field| {
+                    let ident = field.ident.as_ref()?;
+                    let entry = Entry::new(ident.to_string(), ident);
+                    Some(Element::new(entry, field.span()).with_attrs(&field.attrs))
+                })
+                .collect();
+            self.sort(elements, &order);
+        }
+        visit::visit_item_struct(self, node);
+    }
+
+    fn visit_item_impl(&mut self, node: &'ast ItemImpl) {
+        if let Some(order) = sorted_order(&node.attrs) {
+            // Only the functions move, among the places functions were.
+            let elements = node
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    ImplItem::Fn(item) => {
+                        let ident = &item.sig.ident;
+                        Some(Element::new(
+                            Entry::new(ident.to_string(), ident),
+                            item.span(),
+                        ))
+                    }
+                    _ => None,
+                })
+                .collect();
+            self.sort(elements, &order);
+        }
+        visit::visit_item_impl(self, node);
+    }
+
+    fn visit_item_mod(&mut self, node: &'ast ItemMod) {
+        if let (Some(order), Some((_, items))) = (sorted_order(&node.attrs), &node.content) {
+            let elements = items
+                .iter()
+                .filter_map(|item| {
+                    let ident = item_ident(item)?;
+                    Some(Element::new(
+                        Entry::new(ident.to_string(), ident),
+                        item.span(),
+                    ))
+                })
+                .collect();
+            self.sort(elements, &order);
+        }
+        visit::visit_item_mod(self, node);
+    }
+
+    fn visit_item_use(&mut self, node: &'ast ItemUse) {
+        if let Some(order) = sorted_order(&node.attrs) {
+            self.sort_use_tree(&node.tree, &order);
+        }
+        visit::visit_item_use(self, node);
+    }
+
+    fn visit_expr_match(&mut self, node: &'ast ExprMatch) {
+        if let Some(order) = sorted_order(&node.attrs) {
+            self.sort_arms(&node.arms, &order);
+        }
+        visit::visit_expr_match(self, node);
+    }
+}
+
+fn element_cmp(order: &Order, a: &Element, b: &Element) -> Ordering {
+    a.rank
+        .cmp(&b.rank)
+        .then_with(|| order.cmp(&a.entry, &b.entry))
+}
+
+/// The ordering asked for by a `#[sorted]` or `#[sorted::sorted]` attribute,
+/// if there is one.
+fn sorted_order(attrs: &[Attribute]) -> Option<Order> {
+    let attr = attrs.iter().find(|attr| {
+        let path = attr.path();
+        path.is_ident("sorted")
+            || path.segments.len() == 2 && path.segments.iter().all(|s| s.ident == "sorted")
+    })?;
+
+    let mut order = Order::default();
+    if let Meta::List(list) = &attr.meta {
+        list.parse_nested_meta(|meta| order.parse_meta(meta)).ok()?;
+    }
+    order.validate().ok()?;
+    Some(order)
+}
+
+/// Whether a match arm body can be followed by another arm without a comma.
+/// Whether an arm matches anything: `_`, or a binding such as `other`. An
+/// identifier starting with an uppercase letter is taken to be a unit variant
+/// or a constant, as `Eof` is after `use Error::*`.
+fn is_catch_all(pat: &Pat) -> bool {
+    match pat {
+        Pat::Wild(_) => true,
+        Pat::Ident(pat) => {
+            pat.subpat.is_none()
+                && (pat.by_ref.is_some()
+                    || pat.mutability.is_some()
+                    || !pat.ident.to_string().starts_with(char::is_uppercase))
+        }
+        _ => false,
+    }
+}
+
+fn is_block_like(expr: &Expr) -> bool {
+    matches!(
+        expr,
+        Expr::Block(_)
+            | Expr::Const(_)
+            | Expr::ForLoop(_)
+            | Expr::If(_)
+            | Expr::Loop(_)
+            | Expr::Match(_)
+            | Expr::TryBlock(_)
+            | Expr::Unsafe(_)
+            | Expr::While(_)
+    )
+}
+
+fn line_starts(source: &str) -> Vec<usize> {
+    let mut starts = vec![0];
+    starts.extend(source.match_indices('\n').map(|(i, _)| i + 1));
+    starts
+}
+
+/// Prints the changes as a unified diff with a single hunk, spanning from the
+/// first changed line to the last.
+fn print_diff(path: &Path, old: &str, new: &str) {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    // Longest common subsequence of the changed lines, from the end.
+    let mut common = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = if a[i] == b[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let before = &old[prefix.saturating_sub(CONTEXT)..prefix];
+    let after = &old[old.len() - suffix..][..suffix.min(CONTEXT)];
+    let context = before.len() + after.len();
+
+    println!("--- {}", path.display());
+    println!("+++ {}", path.display());
+    println!(
+        "@@ -{},{} +{},{} @@",
+        prefix - before.len() + 1,
+        a.len() + context,
+        prefix - before.len() + 1,
+        b.len() + context,
+    );
+    for line in before {
+        println!(" {}", line);
+    }
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            println!(" {}", a[i]);
+            i += 1;
+            j += 1;
+        } else if j == b.len() || i < a.len() && common[i + 1][j] >= common[i][j + 1] {
+            println!("-{}", a[i]);
+            i += 1;
+        } else {
+            println!("+{}", b[j]);
+            j += 1;
+        }
+    }
+    for line in after {
+        println!(" {}", line);
+    }
+}
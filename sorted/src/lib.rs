@@ -1,9 +1,353 @@
+mod order;
+
+use crate::order::{
+    discriminant, is_group_marker, item_ident, pattern_entry, use_tree_entry, Entry, Order,
+};
 use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::ToTokens;
+use std::cmp::Ordering;
+use syn::visit_mut::{self, VisitMut};
+use syn::{
+    parse_macro_input, Arm, Attribute, Error, ExprMatch, Fields, ImplItem, Item, ItemEnum, ItemFn,
+    Meta, Pat, Result, UseTree,
+};
 
 #[proc_macro_attribute]
 pub fn sorted(args: TokenStream, input: TokenStream) -> TokenStream {
+    let mut order = Order::default();
+    let parser = syn::meta::parser(|meta| order.parse_meta(meta));
+    parse_macro_input!(args with parser);
+    let mut item = parse_macro_input!(input as Item);
+
+    let result = order.validate().and_then(|()| expand(&item, &order));
+
+    // The item is kept either way, so that an ordering error is the only
+    // error rather than the first of many about a missing type.
+    remove_group_markers(&mut item);
+    let mut output = item.to_token_stream();
+    if let Err(err) = result {
+        output.extend(err.into_compile_error());
+    }
+    output.into()
+}
+
+#[proc_macro_attribute]
+pub fn check(args: TokenStream, input: TokenStream) -> TokenStream {
     let _ = args;
-    let _ = input;
+    let mut item = parse_macro_input!(input as ItemFn);
+
+    let mut check = Check { errors: None };
+    check.visit_item_fn_mut(&mut item);
+
+    let mut output = item.to_token_stream();
+    if let Some(err) = check.errors {
+        output.extend(err.into_compile_error());
+    }
+    output.into()
+}
+
+fn expand(item: &Item, order: &Order) -> Result<()> {
+    match item {
+        Item::Enum(item) if order.by_discriminant => check_discriminants(item, order),
+        _ if order.by_discriminant => Err(Error::new(
+            Span::call_site(),
+            "by = \"discriminant\" applies to enums only",
+        )),
+        Item::Enum(item) => check_order(
+            item.variants
+                .iter()
+                .map(|variant| {
+                    Entry::new(variant.ident.to_string(), &variant.ident).with_attrs(&variant.attrs)
+                })
+                .collect(),
+            order,
+        ),
+        Item::Struct(item) => match &item.fields {
+            Fields::Named(fields) => check_order(
+                fields
+                    .named
+                    .iter()
+                    .filter_map(|field| {
+                        let ident = field.ident.as_ref()?;
+                        Some(Entry::new(ident.to_string(), ident).with_attrs(&field.attrs))
+                    })
+                    .collect(),
+                order,
+            ),
+            _ => Err(Error::new(
+                Span::call_site(),
+                "#[sorted] needs a struct with named fields",
+            )),
+        },
+        Item::Impl(item) => check_order(
+            item.items
+                .iter()
+                .filter_map(|item| match item {
+                    ImplItem::Fn(item) => Some(&item.sig.ident),
+                    _ => None,
+                })
+                .map(|ident| Entry::new(ident.to_string(), ident))
+                .collect(),
+            order,
+        ),
+        Item::Mod(item) => match &item.content {
+            Some((_, items)) => check_order(
+                items
+                    .iter()
+                    .filter_map(item_ident)
+                    .map(|ident| Entry::new(ident.to_string(), ident))
+                    .collect(),
+                order,
+            ),
+            None => Err(Error::new(
+                Span::call_site(),
+                "#[sorted] needs a module with its items inline",
+            )),
+        },
+        Item::Use(item) => check_use_tree(&item.tree, order),
+        _ => Err(Error::new(
+            Span::call_site(),
+            "expected enum, struct, impl, mod, use or match expression",
+        )),
+    }
+}
+
+/// Removes the `#[sorted::group]` attributes from the variants or fields of
+/// an item, as the compiler does not know them.
+fn remove_group_markers(item: &mut Item) {
+    match item {
+        Item::Enum(item) => {
+            for variant in &mut item.variants {
+                variant.attrs.retain(|attr| !is_group_marker(attr));
+            }
+        }
+        Item::Struct(item) => {
+            for field in &mut item.fields {
+                field.attrs.retain(|attr| !is_group_marker(attr));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks every `{...}` group in a use tree, nested ones included. `self`, if
+/// present, goes first, as rustfmt puts it.
+fn check_use_tree(tree: &UseTree, order: &Order) -> Result<()> {
+    match tree {
+        UseTree::Path(path) => check_use_tree(&path.tree, order),
+        UseTree::Group(group) => {
+            let mut entries = Vec::new();
+
+            for (i, tree) in group.items.iter().enumerate() {
+                match tree {
+                    UseTree::Name(name) if name.ident == "self" && i == 0 => {}
+                    UseTree::Name(name) if name.ident == "self" => {
+                        return Err(Error::new_spanned(
+                            name,
+                            "`self` should be the first item in the group",
+                        ))
+                    }
+                    tree => entries.push(use_tree_entry(tree)),
+                }
+                check_use_tree(tree, order)?;
+            }
+
+            check_order(entries, order)
+        }
+        UseTree::Name(_) | UseTree::Rename(_) | UseTree::Glob(_) => Ok(()),
+    }
+}
+
+/// Finds the match expressions marked `#[sorted]` in a function, checks their
+/// arms and removes the attribute, which the compiler does not allow on an
+/// expression.
+struct Check {
+    errors: Option<Error>,
+}
+
+impl VisitMut for Check {
+    fn visit_expr_match_mut(&mut self, node: &mut ExprMatch) {
+        if let Some(i) = node
+            .attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("sorted"))
+        {
+            let attr = node.attrs.remove(i);
+            if let Err(err) = check_match(&attr, &node.arms) {
+                match &mut self.errors {
+                    Some(errors) => errors.combine(err),
+                    None => self.errors = Some(err),
+                }
+            }
+            for arm in &mut node.arms {
+                arm.attrs.retain(|attr| !is_group_marker(attr));
+            }
+        }
+
+        visit_mut::visit_expr_match_mut(self, node);
+    }
+}
+
+/// Checks the arms of a match marked with `attr`, `#[sorted]` or
+/// `#[sorted(natural)]` and so on.
+fn check_match(attr: &Attribute, arms: &[Arm]) -> Result<()> {
+    let mut order = Order::default();
+    if let Meta::List(list) = &attr.meta {
+        list.parse_nested_meta(|meta| order.parse_meta(meta))?;
+    }
+    order.validate()?;
+    if order.by_discriminant {
+        return Err(Error::new_spanned(
+            attr,
+            "by = \"discriminant\" applies to enums, not match expressions",
+        ));
+    }
+
+    check_arms(arms, &order)
+}
+
+fn check_arms(arms: &[Arm], order: &Order) -> Result<()> {
+    let mut entries = Vec::new();
+
+    for (i, arm) in arms.iter().enumerate() {
+        match &arm.pat {
+            Pat::Wild(_) if i + 1 == arms.len() => break,
+            Pat::Wild(pat) => {
+                return Err(Error::new_spanned(
+                    pat,
+                    "the wildcard pattern should be the last arm",
+                ))
+            }
+            // Each alternative of `A | B` is checked as if it had its own arm.
+            Pat::Or(pat) => {
+                for (j, case) in pat.cases.iter().enumerate() {
+                    let entry = pattern_entry(case)?;
+                    entries.push(if j == 0 {
+                        entry.with_attrs(&arm.attrs)
+                    } else {
+                        entry
+                    });
+                }
+            }
+            pat => entries.push(pattern_entry(pat)?.with_attrs(&arm.attrs)),
+        }
+    }
+
+    check_order(entries, order)
+}
+
+/// Checks that the enum's discriminants ascend. A variant without one takes
+/// the value after the previous variant's, as in Rust.
+fn check_discriminants(item: &ItemEnum, order: &Order) -> Result<()> {
+    let mut next = 0;
+    let mut entries = Vec::new();
+
+    for variant in &item.variants {
+        let value = match &variant.discriminant {
+            Some((_, expr)) => discriminant(expr)?,
+            None => next,
+        };
+        next = value + 1;
+        entries.push(Entry {
+            discriminant: value,
+            ..Entry::new(variant.ident.to_string(), &variant.ident).with_attrs(&variant.attrs)
+        });
+    }
+
+    check_order(entries, order)
+}
+
+/// Checks each group of entries, a new one starting at every entry marked
+/// `#[sorted::group]`, and with `groups_sorted` the order of the groups by
+/// their first entries. Every misplaced entry gets an error of its own.
+fn check_order(entries: Vec<Entry>, order: &Order) -> Result<()> {
+    let mut groups: Vec<Vec<&Entry>> = Vec::new();
+    for entry in &entries {
+        match groups.last_mut() {
+            Some(group) if !entry.starts_group => group.push(entry),
+            _ => groups.push(vec![entry]),
+        }
+    }
+
+    let mut errors = Vec::new();
+    for group in &groups {
+        for (entry, place, other) in misplaced(group, order) {
+            errors.push(Error::new_spanned(
+                entry.tokens,
+                format!("{} should sort {} {}", entry.name, place, other.name),
+            ));
+        }
+    }
+
+    if order.groups_sorted {
+        let heads: Vec<&Entry> = groups.iter().map(|group| group[0]).collect();
+        for (entry, place, other) in misplaced(&heads, order) {
+            errors.push(Error::new_spanned(
+                entry.tokens,
+                format!(
+                    "the group starting with {} should sort {} the group starting with {}",
+                    entry.name, place, other.name,
+                ),
+            ));
+        }
+    }
+
+    match errors.into_iter().reduce(|mut errors, err| {
+        errors.combine(err);
+        errors
+    }) {
+        Some(errors) => Err(errors),
+        None => Ok(()),
+    }
+}
+
+/// Finds the entries out of place: all but a longest run of entries already
+/// in order, preferring to keep the earliest entries when there is a choice.
+/// Each comes with where it belongs, `"before"` the first entry of the run
+/// that sorts after it, or `"after"` the run's last entry.
+fn misplaced<'a, 'b>(
+    entries: &[&'a Entry<'b>],
+    order: &Order,
+) -> Vec<(&'a Entry<'b>, &'static str, &'a Entry<'b>)> {
+    let in_order = |i: usize, j: usize| order.cmp(entries[i], entries[j]) != Ordering::Greater;
+
+    // The length of the longest run in order starting at each entry.
+    let mut longest = vec![1; entries.len()];
+    for i in (0..entries.len()).rev() {
+        for j in i + 1..entries.len() {
+            if in_order(i, j) {
+                longest[i] = longest[i].max(longest[j] + 1);
+            }
+        }
+    }
+
+    let mut kept = vec![false; entries.len()];
+    let mut remaining = longest.iter().copied().max().unwrap_or(0);
+    let mut previous = None;
+    for i in 0..entries.len() {
+        if remaining > 0 && longest[i] == remaining && previous.is_none_or(|p| in_order(p, i)) {
+            kept[i] = true;
+            remaining -= 1;
+            previous = Some(i);
+        }
+    }
 
-    unimplemented!()
+    let run: Vec<&Entry> = (0..entries.len())
+        .filter(|&i| kept[i])
+        .map(|i| entries[i])
+        .collect();
+    (0..entries.len())
+        .filter(|&i| !kept[i])
+        .map(|i| {
+            let entry = entries[i];
+            match run
+                .iter()
+                .find(|other| order.cmp(entry, other) == Ordering::Less)
+            {
+                Some(other) => (entry, "before", *other),
+                None => (entry, "after", run[run.len() - 1]),
+            }
+        })
+        .collect()
 }
@@ -0,0 +1,233 @@
+//! How names are compared, and how to find the name of each thing that
+//! `#[sorted]` puts in order. Shared by the macros and the `sorted-fix`
+//! binary.
+
+use proc_macro2::{Ident, Span};
+use quote::ToTokens;
+use std::cmp::Ordering;
+use syn::meta::ParseNestedMeta;
+use syn::{
+    Attribute, Error, Expr, ExprLit, ExprUnary, Item, Lit, LitStr, Pat, Path, Result, UnOp, UseTree,
+};
+
+/// A name whose position is checked, and the tokens an error about it should
+/// point at.
+pub(crate) struct Entry<'a> {
+    pub(crate) name: String,
+    /// The enum discriminant, for `#[sorted(by = "discriminant")]`.
+    pub(crate) discriminant: i128,
+    pub(crate) tokens: &'a dyn ToTokens,
+    /// Whether the entry is marked `#[sorted::group]`, starting a new group
+    /// that is ordered separately from the entries above it.
+    pub(crate) starts_group: bool,
+}
+
+impl<'a> Entry<'a> {
+    pub(crate) fn new(name: String, tokens: &'a dyn ToTokens) -> Self {
+        Entry {
+            name,
+            discriminant: 0,
+            tokens,
+            starts_group: false,
+        }
+    }
+
+    /// Marks the entry as starting a group if `attrs` contains
+    /// `#[sorted::group]`.
+    pub(crate) fn with_attrs(mut self, attrs: &[Attribute]) -> Self {
+        self.starts_group = attrs.iter().any(is_group_marker);
+        self
+    }
+}
+
+pub(crate) fn is_group_marker(attr: &Attribute) -> bool {
+    let mut segments = attr.path().segments.iter();
+    matches!(
+        (segments.next(), segments.next(), segments.next()),
+        (Some(first), Some(second), None) if first.ident == "sorted" && second.ident == "group"
+    )
+}
+
+/// How entries are compared: `#[sorted]`, `#[sorted(natural)]`,
+/// `#[sorted(case_insensitive)]` or `#[sorted(by = "discriminant")]`, plus
+/// `#[sorted(groups_sorted)]` to have the groups in order too.
+#[derive(Default)]
+pub(crate) struct Order {
+    /// Runs of digits compare by their numeric value, so `Item2` sorts before
+    /// `Item10`.
+    natural: bool,
+    /// Letters compare ignoring case, so `apple` sorts before `Zebra`.
+    case_insensitive: bool,
+    pub(crate) by_discriminant: bool,
+    /// Groups started by `#[sorted::group]` are ordered by their first entry.
+    pub(crate) groups_sorted: bool,
+}
+
+impl Order {
+    pub(crate) fn parse_meta(&mut self, meta: ParseNestedMeta) -> Result<()> {
+        if meta.path.is_ident("natural") {
+            self.natural = true;
+        } else if meta.path.is_ident("case_insensitive") {
+            self.case_insensitive = true;
+        } else if meta.path.is_ident("groups_sorted") {
+            self.groups_sorted = true;
+        } else if meta.path.is_ident("by") {
+            let by: LitStr = meta.value()?.parse()?;
+            match by.value().as_str() {
+                "name" => {}
+                "discriminant" => self.by_discriminant = true,
+                _ => {
+                    return Err(Error::new_spanned(
+                        by,
+                        "expected `by = \"name\"` or `by = \"discriminant\"`",
+                    ))
+                }
+            }
+        } else {
+            return Err(meta.error(
+                "unsupported #[sorted] option; expected `natural`, `case_insensitive`, \
+                 `groups_sorted` or `by = \"discriminant\"`",
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn validate(&self) -> Result<()> {
+        if self.by_discriminant && (self.natural || self.case_insensitive) {
+            return Err(Error::new(
+                Span::call_site(),
+                "by = \"discriminant\" cannot be combined with `natural` or \
+                 `case_insensitive`",
+            ));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn cmp(&self, a: &Entry, b: &Entry) -> Ordering {
+        if self.by_discriminant {
+            return a.discriminant.cmp(&b.discriminant);
+        }
+
+        let (x, y) = if self.case_insensitive {
+            (a.name.to_lowercase(), b.name.to_lowercase())
+        } else {
+            (a.name.clone(), b.name.clone())
+        };
+        let ordering = if self.natural {
+            natural_cmp(&x, &y)
+        } else {
+            x.cmp(&y)
+        };
+
+        // Names equal but for case or leading zeros still need an order.
+        ordering.then_with(|| a.name.cmp(&b.name))
+    }
+}
+
+/// Compares runs of digits by their numeric value and everything else
+/// character by character.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (a, b) = (chunks(a), chunks(b));
+
+    for (x, y) in a.iter().zip(&b) {
+        let is_number = |chunk: &str| chunk.starts_with(|c: char| c.is_ascii_digit());
+        let ordering = if is_number(x) && is_number(y) {
+            let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+            x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+        } else {
+            x.cmp(y)
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    a.len().cmp(&b.len())
+}
+
+/// Splits a name into runs of digits and single other characters.
+fn chunks(name: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = name;
+
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() {
+            rest.find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len())
+        } else {
+            c.len_utf8()
+        };
+        chunks.push(&rest[..len]);
+        rest = &rest[len..];
+    }
+
+    chunks
+}
+
+/// The name a module item is known by. Items without one, such as `impl`
+/// blocks and `use` declarations, may go anywhere.
+pub(crate) fn item_ident(item: &Item) -> Option<&Ident> {
+    match item {
+        Item::Const(item) => Some(&item.ident),
+        Item::Enum(item) => Some(&item.ident),
+        Item::ExternCrate(item) => Some(&item.ident),
+        Item::Fn(item) => Some(&item.sig.ident),
+        Item::Macro(item) => item.ident.as_ref(),
+        Item::Mod(item) => Some(&item.ident),
+        Item::Static(item) => Some(&item.ident),
+        Item::Struct(item) => Some(&item.ident),
+        Item::Trait(item) => Some(&item.ident),
+        Item::TraitAlias(item) => Some(&item.ident),
+        Item::Type(item) => Some(&item.ident),
+        Item::Union(item) => Some(&item.ident),
+        _ => None,
+    }
+}
+
+pub(crate) fn use_tree_entry(tree: &UseTree) -> Entry<'_> {
+    match tree {
+        UseTree::Path(path) => Entry::new(path.ident.to_string(), &path.ident),
+        UseTree::Name(name) => Entry::new(name.ident.to_string(), &name.ident),
+        UseTree::Rename(rename) => Entry::new(rename.ident.to_string(), &rename.ident),
+        UseTree::Glob(glob) => Entry::new("*".to_owned(), glob),
+        UseTree::Group(group) => Entry::new("{".to_owned(), group),
+    }
+}
+
+pub(crate) fn pattern_entry(pat: &Pat) -> Result<Entry<'_>> {
+    let path = match pat {
+        Pat::Path(pat) => &pat.path,
+        Pat::TupleStruct(pat) => &pat.path,
+        Pat::Struct(pat) => &pat.path,
+        Pat::Ident(pat) if pat.subpat.is_none() => {
+            return Ok(Entry::new(pat.ident.to_string(), &pat.ident));
+        }
+        pat => return Err(Error::new_spanned(pat, "unsupported by #[sorted]")),
+    };
+    Ok(Entry::new(path_to_string(path), path))
+}
+
+pub(crate) fn discriminant(expr: &Expr) -> Result<i128> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => lit.base10_parse(),
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => discriminant(expr).map(|value| -value),
+        _ => Err(Error::new_spanned(
+            expr,
+            "#[sorted(by = \"discriminant\")] needs integer literal discriminants",
+        )),
+    }
+}
+
+pub(crate) fn path_to_string(path: &Path) -> String {
+    path.segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
@@ -1,13 +1,14 @@
-// The #[sorted] macro is only defined to work on enum types, so this is a test
-// to ensure that when it's attached to a struct (or anything else) it produces
-// some reasonable error. Your macro will need to look into the syn::Item that
-// it parsed to ensure that it represents an enum, returning an error for any
-// other type of Item such as a struct.
+// The #[sorted] macro is only defined to work on items that contain a list of
+// names: enums, structs, impl blocks, modules and use trees. This is a test to
+// ensure that when it's attached to anything else, such as a type alias, it
+// produces some reasonable error. Your macro will need to look into the
+// syn::Item that it parsed to ensure that it is one of those, returning an
+// error for any other type of Item.
 //
 // This is an exercise in exploring how to return errors from procedural macros.
 // The goal is to produce an understandable error message which is tailored to
 // this specific macro (saying that #[sorted] cannot be applied to things other
-// than enum). For this you'll want to look at the syn::Error type, how to
+// than those items). For this you'll want to look at the syn::Error type, how to
 // construct it, and how to return it.
 //
 // Notice that the return value of an attribute macro is simply a TokenStream,
@@ -29,10 +30,7 @@
 use sorted::sorted;
 
 #[sorted]
-pub struct Error {
-    kind: ErrorKind,
-    message: String,
-}
+pub type Error = ErrorKind;
 
 enum ErrorKind {
     Io,
@@ -0,0 +1,67 @@
+// Plain string comparison puts `Item10` before `Item2`, and every uppercase
+// letter before every lowercase one. Arguments to #[sorted] choose another
+// ordering, for enums and match expressions alike:
+//
+//   - `#[sorted(natural)]` compares runs of digits by their numeric value;
+//   - `#[sorted(case_insensitive)]` compares letters ignoring case;
+//   - the two can be combined;
+//   - `#[sorted(by = "discriminant")]` checks that an enum's discriminants
+//     ascend, whatever its variants are called. A variant without an explicit
+//     discriminant takes the one after the previous variant's, as in Rust.
+
+use sorted::sorted;
+
+#[sorted(natural)]
+pub enum Register {
+    Reg1,
+    Reg2,
+    Reg10,
+    Reg11,
+    RegA,
+}
+
+#[sorted(case_insensitive)]
+#[allow(non_camel_case_types)]
+pub enum Fruit {
+    apple,
+    Banana,
+    cherry,
+    Date,
+}
+
+#[sorted(natural, case_insensitive)]
+#[allow(non_camel_case_types)]
+pub enum Version {
+    v1,
+    V2,
+    v10,
+}
+
+#[sorted(by = "discriminant")]
+#[repr(i16)]
+pub enum Opcode {
+    Nop = -1,
+    Load,
+    Store = 0x10,
+    Jump,
+    Call = 0x40,
+    Add = 0x41,
+}
+
+#[sorted::check]
+fn width(register: Register) -> u32 {
+    use self::Register::*;
+
+    #[sorted(natural)]
+    match register {
+        Reg1 | Reg2 => 8,
+        Reg10 => 16,
+        Reg11 => 32,
+        RegA => 64,
+    }
+}
+
+fn main() {
+    assert_eq!(width(Register::Reg10), 16);
+    assert_eq!(Opcode::Jump as i16, 0x11);
+}
@@ -0,0 +1,39 @@
+// Violations of the other orderings are reported with the same diagnostic as
+// plain #[sorted], and discriminants that are not integer literals or options
+// that don't go together are rejected.
+
+use sorted::sorted;
+
+#[sorted(natural)]
+pub enum Register {
+    Reg1,
+    Reg10,
+    Reg2,
+}
+
+#[sorted(case_insensitive)]
+#[allow(non_camel_case_types)]
+pub enum Fruit {
+    banana,
+    Apple,
+}
+
+#[sorted(by = "discriminant")]
+pub enum Opcode {
+    Load = 2,
+    Nop = 1,
+}
+
+const BASE: isize = 4;
+
+#[sorted(by = "discriminant")]
+pub enum Offset {
+    Start = BASE,
+}
+
+#[sorted(by = "discriminant", natural)]
+pub enum Mixed {
+    A,
+}
+
+fn main() {}
@@ -0,0 +1,69 @@
+// Besides enums, #[sorted] checks the names inside a few other items:
+//
+//   - the fields of a struct;
+//   - the associated functions of an impl block;
+//   - the named items of an inline module, in any mix of kinds. Items without
+//     a name of their own, such as impl blocks and use declarations, may go
+//     anywhere;
+//   - every `{...}` group of a use declaration, nested ones included. As in
+//     rustfmt's output, `self` goes first.
+//
+// Ordering options such as `natural` apply to these items too.
+
+use sorted::sorted;
+
+#[sorted]
+pub struct Config {
+    depth: usize,
+    name: String,
+    verbose: bool,
+}
+
+pub struct Counter(usize);
+
+#[sorted]
+impl Counter {
+    const START: usize = 0;
+
+    pub fn get(&self) -> usize {
+        self.0
+    }
+
+    pub fn increment(&mut self) {
+        self.0 += 1;
+    }
+
+    pub fn new() -> Self {
+        Counter(Self::START)
+    }
+}
+
+#[sorted(natural)]
+mod registers {
+    pub const REG2: u8 = 2;
+    pub const REG10: u8 = 10;
+    pub struct Register;
+
+    impl super::Counter {
+        pub fn reset(&mut self) {
+            self.0 = 0;
+        }
+    }
+
+    pub fn read() {}
+}
+
+#[sorted]
+#[allow(unused_imports)]
+use std::{
+    collections::{self, BTreeMap, HashMap},
+    fmt::{Debug, Display},
+    io,
+};
+
+fn main() {
+    let mut counter = Counter::new();
+    counter.increment();
+    counter.reset();
+    assert_eq!(counter.get(), 0);
+}
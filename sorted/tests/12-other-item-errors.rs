@@ -0,0 +1,42 @@
+// Ordering errors in structs, impl blocks, modules and use trees point at the
+// misplaced name, just as they do for enums.
+
+#![allow(unused_imports)]
+
+use sorted::sorted;
+
+#[sorted]
+pub struct Config {
+    name: String,
+    depth: usize,
+}
+
+pub struct Counter;
+
+#[sorted]
+impl Counter {
+    pub fn new() -> Self {
+        Counter
+    }
+
+    pub fn get(&self) -> usize {
+        0
+    }
+}
+
+#[sorted]
+mod items {
+    pub fn write() {}
+    pub fn read() {}
+}
+
+#[sorted]
+use std::collections::{HashMap, BTreeMap};
+
+#[sorted]
+use std::io::{Read, self};
+
+#[sorted]
+pub struct Point(i32, i32);
+
+fn main() {}
@@ -0,0 +1,59 @@
+// Long enums are often organized in groups, each sorted on its own. Marking a
+// variant, field or match arm with #[sorted::group] starts a new group there:
+// ordering is only checked between entries of the same group. The marker is
+// removed by #[sorted] and #[sorted::check], so the compiler never sees it.
+//
+// With `#[sorted(groups_sorted)]` the groups also have to be in order, by
+// their first entries.
+
+use sorted::sorted;
+
+#[sorted]
+pub enum Error {
+    // Reading.
+    Eof,
+    Io,
+    // Parsing.
+    #[sorted::group]
+    Lex,
+    Parse,
+    // Everything else.
+    #[sorted::group]
+    Internal,
+    Unsupported,
+}
+
+#[sorted(groups_sorted)]
+pub struct Options {
+    name: String,
+    verbose: bool,
+    #[sorted::group]
+    width: usize,
+    x: i32,
+    y: i32,
+}
+
+#[sorted::check]
+fn category(error: &Error) -> &'static str {
+    #[sorted(groups_sorted)]
+    match error {
+        Error::Eof | Error::Io => "read",
+        #[sorted::group]
+        Error::Internal => "other",
+        Error::Unsupported => "other",
+        #[sorted::group]
+        Error::Lex => "parse",
+        Error::Parse => "parse",
+    }
+}
+
+fn main() {
+    assert_eq!(category(&Error::Lex), "parse");
+    let _ = Options {
+        name: String::new(),
+        verbose: false,
+        width: 80,
+        x: 0,
+        y: 0,
+    };
+}
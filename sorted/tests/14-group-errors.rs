@@ -0,0 +1,38 @@
+// Within a group the usual ordering errors apply. With `groups_sorted`, a
+// group whose first entry sorts before the first entry of an earlier group is
+// reported at its first entry.
+
+use sorted::sorted;
+
+#[sorted]
+pub enum Error {
+    Io,
+    Eof,
+    #[sorted::group]
+    Lex,
+    Parse,
+}
+
+#[sorted(groups_sorted)]
+pub enum Kind {
+    #[sorted::group]
+    Parse,
+    Syntax,
+    #[sorted::group]
+    Io,
+    Network,
+}
+
+#[sorted::check]
+fn describe(kind: Kind) -> &'static str {
+    #[sorted]
+    match kind {
+        Kind::Io => "io",
+        #[sorted::group]
+        Kind::Syntax => "syntax",
+        Kind::Parse => "parse",
+        Kind::Network => "network",
+    }
+}
+
+fn main() {}
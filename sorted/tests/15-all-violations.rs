@@ -0,0 +1,43 @@
+// Rather than stopping at the first entry out of place, #[sorted] reports
+// every one of them, so that a freshly annotated enum can be put in order in
+// one go.
+//
+// The macro finds the longest run of entries that are already in order,
+// keeping the earliest entries where there is a choice, and reports each of
+// the others once, naming the entry of that run it should sort before, or the
+// last one it should sort after when it belongs at the end.
+
+use sorted::sorted;
+
+#[sorted]
+pub enum Error {
+    Config,
+    Io,
+    Eof,
+    Network,
+    Decode,
+    Parse,
+    Auth,
+    Timeout,
+}
+
+#[sorted]
+pub enum Level {
+    Warn,
+    Debug,
+    Error,
+}
+
+#[sorted::check]
+fn retry(error: &Error) -> bool {
+    #[sorted]
+    match error {
+        Error::Timeout => true,
+        Error::Auth | Error::Config => false,
+        Error::Network => true,
+        Error::Decode => false,
+        _ => false,
+    }
+}
+
+fn main() {}
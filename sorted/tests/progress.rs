@@ -1,12 +1,19 @@
 #[test]
 fn tests() {
     let t = trybuild::TestCases::new();
-    //t.pass("tests/01-parse-enum.rs");
-    //t.compile_fail("tests/02-not-enum.rs");
-    //t.compile_fail("tests/03-out-of-order.rs");
-    //t.compile_fail("tests/04-variants-with-data.rs");
-    //t.compile_fail("tests/05-match-expr.rs");
-    //t.compile_fail("tests/06-pattern-path.rs");
-    //t.compile_fail("tests/07-unrecognized-pattern.rs");
-    //t.pass("tests/08-underscore.rs");
+    t.pass("tests/01-parse-enum.rs");
+    t.compile_fail("tests/02-not-enum.rs");
+    t.compile_fail("tests/03-out-of-order.rs");
+    t.compile_fail("tests/04-variants-with-data.rs");
+    t.compile_fail("tests/05-match-expr.rs");
+    t.compile_fail("tests/06-pattern-path.rs");
+    t.compile_fail("tests/07-unrecognized-pattern.rs");
+    t.pass("tests/08-underscore.rs");
+    t.pass("tests/09-ordering-modes.rs");
+    t.compile_fail("tests/10-ordering-mode-errors.rs");
+    t.pass("tests/11-other-items.rs");
+    t.compile_fail("tests/12-other-item-errors.rs");
+    t.pass("tests/13-groups.rs");
+    t.compile_fail("tests/14-group-errors.rs");
+    t.compile_fail("tests/15-all-violations.rs");
 }
@@ -0,0 +1,63 @@
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn sorted_fix() {
+    let input = Path::new("tests/sorted-fix/input.rs");
+    let expected = fs::read_to_string("tests/sorted-fix/expected.rs").unwrap();
+    let expected = expected.split_once("\n\n").unwrap().1;
+
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("sorted-fix.rs");
+    let input = fs::read_to_string(input).unwrap();
+    fs::write(&path, input.split_once("\n\n").unwrap().1).unwrap();
+    let sorted_fix = || Command::new(env!("CARGO_BIN_EXE_sorted-fix"));
+
+    // --check reports the changes without making them.
+    let check = sorted_fix().arg("--check").arg(&path).output().unwrap();
+    assert_eq!(check.status.code(), Some(1));
+    let diff = String::from_utf8(check.stdout).unwrap();
+    assert!(diff.contains("-    Syntax { line: usize },\n"), "{}", diff);
+    assert!(diff.contains("+enum Op { A = 1, B = 2 }\n"), "{}", diff);
+
+    let fix = sorted_fix().arg(&path).output().unwrap();
+    assert!(fix.status.success());
+    assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+
+    let check = sorted_fix().arg("--check").arg(&path).output().unwrap();
+    assert_eq!(check.status.code(), Some(0));
+    assert!(check.stdout.is_empty());
+
+    let t = trybuild::TestCases::new();
+    t.pass("tests/sorted-fix/expected.rs");
+}
+
+#[test]
+fn sorted_fix_catch_all() {
+    let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("sorted-fix-catch-all.rs");
+    let input = "\
+fn f(e: &Error) -> u8 {
+    #[sorted]
+    match e {
+        Error::Io(_) => 2,
+        other => 0,
+        Error::Eof => 1,
+    }
+}
+";
+    fs::write(&path, input).unwrap();
+
+    // Moving `Error::Eof` ahead of `other` would change what the match does.
+    let fix = Command::new(env!("CARGO_BIN_EXE_sorted-fix"))
+        .arg(&path)
+        .output()
+        .unwrap();
+    assert!(fix.status.success());
+    let stderr = String::from_utf8(fix.stderr).unwrap();
+    assert!(
+        stderr.contains("5:9: cannot sort arms after a catch-all arm"),
+        "{}",
+        stderr,
+    );
+    assert_eq!(fs::read_to_string(&path).unwrap(), input);
+}
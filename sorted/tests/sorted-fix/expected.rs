@@ -0,0 +1,65 @@
+// What the sorted-fix binary makes of input.rs. Besides matching the binary's
+// output, it must pass the #[sorted] checks.
+
+#![allow(dead_code, unused_imports)]
+
+use sorted::sorted;
+
+#[sorted]
+pub enum Error {
+    /// The file ended early.
+    Eof,
+    Fmt,
+    #[allow(dead_code)]
+    Io(std::io::Error),
+    // Syntax problems.
+    Syntax { line: usize },
+}
+
+#[sorted]
+pub enum Token {
+    Arrow,
+    Comma(u8), // trailing Comma
+    Ident, // trailing Ident
+}
+
+#[sorted(groups_sorted)]
+pub enum Kind {
+    #[sorted::group]
+    Eof,
+    Io,
+    #[sorted::group]
+    Lex,
+    Parse,
+}
+
+#[sorted]
+use std::{collections::HashMap, fmt, io::{self, Read, Write}};
+
+#[sorted::check]
+fn f(e: &Error) -> u8 {
+    #[sorted]
+    match e {
+        Error::Eof | Error::Fmt => 1,
+        Error::Io(_) => { 2 }
+        Error::Syntax { .. } => 3,
+    }
+}
+
+#[sorted(by = "discriminant")]
+enum Op { A = 1, B = 2 }
+
+pub struct Counter;
+
+#[sorted]
+impl Counter {
+    pub fn get(&self) -> usize {
+        Self::START
+    }
+
+    const START: usize = 0;
+
+    pub fn reset(&mut self) {}
+}
+
+fn main() {}
@@ -0,0 +1,66 @@
+// Input for the sorted-fix binary, with enums, a use tree, a match and an
+// impl block out of order. tests/sorted-fix.rs expects it to come out as
+// expected.rs.
+
+#![allow(dead_code, unused_imports)]
+
+use sorted::sorted;
+
+#[sorted]
+pub enum Error {
+    /// The file ended early.
+    Eof,
+    // Syntax problems.
+    Syntax { line: usize },
+    #[allow(dead_code)]
+    Io(std::io::Error),
+    Fmt
+}
+
+#[sorted]
+pub enum Token {
+    Ident, // trailing Ident
+    Comma(u8), // trailing Comma
+    Arrow
+}
+
+#[sorted(groups_sorted)]
+pub enum Kind {
+    #[sorted::group]
+    Parse,
+    Lex,
+    #[sorted::group]
+    Io,
+    Eof,
+}
+
+#[sorted]
+use std::{io::{Write, self, Read}, collections::HashMap, fmt};
+
+#[sorted::check]
+fn f(e: &Error) -> u8 {
+    #[sorted]
+    match e {
+        Error::Syntax { .. } => 3,
+        Error::Io(_) => { 2 }
+        Error::Fmt | Error::Eof => 1
+    }
+}
+
+#[sorted(by = "discriminant")]
+enum Op { B = 2, A = 1 }
+
+pub struct Counter;
+
+#[sorted]
+impl Counter {
+    pub fn reset(&mut self) {}
+
+    const START: usize = 0;
+
+    pub fn get(&self) -> usize {
+        Self::START
+    }
+}
+
+fn main() {}